@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fs,
+    path::Path,
+    time::SystemTime,
+};
+
+use crate::{
+    session::{data_dir, document_key},
+    JsonPointer,
+};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub path: Vec<JsonPointer>,
+    pub label: String,
+    pub created_at: SystemTime,
+}
+
+/// Bookmarks of all documents in `bookmarks.json` of the data directory, by document key
+#[derive(Default, Serialize, Deserialize)]
+pub struct BookmarkStore {
+    documents: HashMap<String, Vec<Bookmark>>,
+}
+
+impl BookmarkStore {
+    /// Stored bookmarks, none when the file is missing or can't be read
+    pub fn load() -> Self {
+        data_dir()
+            .and_then(|dir| fs::read_to_string(dir.join("bookmarks.json")).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let dir = data_dir().ok_or("no data directory")?;
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("bookmarks.json"), serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Bookmarks of the document, oldest first
+    pub fn get(&self, document: &Path) -> Vec<Bookmark> {
+        document_key(document)
+            .and_then(|key| self.documents.get(&key).cloned())
+            .unwrap_or_default()
+    }
+
+    /// Paths of the bookmarks of the document
+    pub fn paths(&self, document: &Path) -> HashSet<Vec<JsonPointer>> {
+        self.get(document)
+            .into_iter()
+            .map(|bookmark| bookmark.path)
+            .collect()
+    }
+
+    /// Adds the bookmark unless its node is already bookmarked, tells whether it was added
+    pub fn add(&mut self, document: &Path, bookmark: Bookmark) -> bool {
+        let Some(key) = document_key(document) else {
+            return false;
+        };
+        let bookmarks = self.documents.entry(key).or_default();
+        if bookmarks
+            .iter()
+            .any(|existing| existing.path == bookmark.path)
+        {
+            return false;
+        }
+        bookmarks.push(bookmark);
+        true
+    }
+
+    /// Removes the bookmark of its node, or adds it when there is none, tells whether the
+    /// node is bookmarked now
+    pub fn toggle(&mut self, document: &Path, bookmark: Bookmark) -> bool {
+        let bookmarked = self
+            .get(document)
+            .iter()
+            .any(|existing| existing.path == bookmark.path);
+        if bookmarked {
+            self.remove(document, &bookmark.path);
+            false
+        } else {
+            self.add(document, bookmark)
+        }
+    }
+
+    pub fn remove(&mut self, document: &Path, path: &[JsonPointer]) {
+        let Some(key) = document_key(document) else {
+            return;
+        };
+        if let Some(bookmarks) = self.documents.get_mut(&key) {
+            bookmarks.retain(|bookmark| bookmark.path != path);
+            if bookmarks.is_empty() {
+                self.documents.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggles_bookmarks() {
+        let document = Path::new("Cargo.toml");
+        let bookmark = |key: &str| Bookmark {
+            path: vec![JsonPointer::ObjectKey(key.to_string())],
+            label: String::new(),
+            created_at: SystemTime::now(),
+        };
+        let mut store = BookmarkStore::default();
+        assert!(store.toggle(document, bookmark("a")));
+        assert!(store.toggle(document, bookmark("b")));
+        assert!(!store.add(document, bookmark("a")));
+        assert!(!store.toggle(document, bookmark("a")));
+        assert_eq!(store.paths(document), HashSet::from([bookmark("b").path]));
+        assert!(!store.toggle(document, bookmark("b")));
+        assert!(store.documents.is_empty());
+        assert!(!store.toggle(Path::new("missing.json"), bookmark("a")));
+    }
+}
@@ -0,0 +1,185 @@
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Deserializer};
+use std::{env, fs, io, path::PathBuf};
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub keys: KeyMap,
+    pub search: SearchConfig,
+}
+
+/// `[search]` section, defaults of the search prompt toggles
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchConfig {
+    pub ignore_case: bool,
+}
+
+macro_rules! key_map {
+    ($($name:ident = $key:expr,)*) => {
+        /// Key bound to every action, `None` when unbound (empty string in config)
+        #[derive(Deserialize)]
+        #[serde(default, deny_unknown_fields)]
+        pub struct KeyMap {
+            $(
+                #[serde(deserialize_with = "deserialize_key")]
+                pub $name: Option<KeyCode>,
+            )*
+        }
+
+        impl Default for KeyMap {
+            fn default() -> Self {
+                Self {
+                    $($name: Some($key),)*
+                }
+            }
+        }
+
+        impl KeyMap {
+            /// `[keys]` section of the config file
+            pub fn to_toml(&self) -> String {
+                let mut toml = String::from("[keys]\n");
+                $(
+                    let key = self.$name.map(key_name).unwrap_or_default();
+                    toml += &format!("{} = \"{key}\"\n", stringify!($name));
+                )*
+                toml
+            }
+        }
+    };
+}
+
+key_map! {
+    quit = KeyCode::Char('q'),
+    command_palette = KeyCode::Char('c'),
+    toggle = KeyCode::Enter,
+    toggle_alt = KeyCode::Char(' '),
+    left = KeyCode::Left,
+    right = KeyCode::Right,
+    down = KeyCode::Down,
+    up = KeyCode::Up,
+    first = KeyCode::Home,
+    last = KeyCode::End,
+    page_down = KeyCode::PageDown,
+    page_up = KeyCode::PageUp,
+    scroll_left = KeyCode::Char('<'),
+    scroll_right = KeyCode::Char('>'),
+    expand_all = KeyCode::Char('e'),
+    collapse_all = KeyCode::Char('E'),
+    sort_keys = KeyCode::Char('o'),
+    sort_types = KeyCode::Char('O'),
+    timestamps = KeyCode::Char('t'),
+    number_format = KeyCode::Char('#'),
+    search = KeyCode::Char('/'),
+    search_subtree = KeyCode::Char('?'),
+    narrow = KeyCode::Char('f'),
+    next_match = KeyCode::Char('n'),
+    prev_match = KeyCode::Char('N'),
+    results = KeyCode::Char('R'),
+    next_same_key = KeyCode::Char(']'),
+    prev_same_key = KeyCode::Char('['),
+    clear_search = KeyCode::Esc,
+    goto = KeyCode::Char('g'),
+    filter = KeyCode::Char('|'),
+    pipe = KeyCode::Char('!'),
+    query = KeyCode::Char(':'),
+    copy = KeyCode::Char('y'),
+    copy_pointer = KeyCode::Char('Y'),
+    nested = KeyCode::Char('J'),
+    base64 = KeyCode::Char('d'),
+    url_decode = KeyCode::Char('u'),
+    bookmark = KeyCode::Char('b'),
+    bookmarks = KeyCode::Char('B'),
+    toggle_bookmark = KeyCode::Char('m'),
+    next_bookmark = KeyCode::Char('\''),
+    prev_bookmark = KeyCode::Char('"'),
+    edit = KeyCode::Char('i'),
+    reload = KeyCode::Char('r'),
+    export = KeyCode::Char('s'),
+    write_tree = KeyCode::Char('W'),
+    fullscreen = KeyCode::Char('v'),
+    print = KeyCode::Char('p'),
+    paste = KeyCode::Char('P'),
+    switch_pane = KeyCode::Tab,
+    close_tab = KeyCode::Char('x'),
+}
+
+impl Config {
+    pub fn to_toml(&self) -> String {
+        format!(
+            "{}\n[search]\nignore_case = {}\n",
+            self.keys.to_toml(),
+            self.search.ignore_case
+        )
+    }
+}
+
+/// `$XDG_CONFIG_HOME/json_viewer/config.toml`, or under `~/.config`
+pub fn config_path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(dir.join(env!("CARGO_PKG_NAME")).join("config.toml"))
+}
+
+/// Loads the config file, defaults when there is none
+pub fn load_config() -> Result<Config, String> {
+    let Some(path) = config_path() else {
+        return Ok(Config::default());
+    };
+    match fs::read_to_string(&path) {
+        Ok(text) => toml::from_str(&text).map_err(|err| format!("{}: {err}", path.display())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(err) => Err(format!("{}: {err}", path.display())),
+    }
+}
+
+fn deserialize_key<'de, D>(deserializer: D) -> Result<Option<KeyCode>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let name = String::deserialize(deserializer)?;
+    if name.is_empty() {
+        return Ok(None);
+    }
+    parse_key(&name)
+        .map(Some)
+        .ok_or_else(|| serde::de::Error::custom(format!("unknown key '{name}'")))
+}
+
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    let code = match name {
+        "Enter" => KeyCode::Enter,
+        "Space" => KeyCode::Char(' '),
+        "Tab" => KeyCode::Tab,
+        "BackTab" => KeyCode::BackTab,
+        "Esc" => KeyCode::Esc,
+        "Backspace" => KeyCode::Backspace,
+        "Delete" => KeyCode::Delete,
+        "Insert" => KeyCode::Insert,
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Home" => KeyCode::Home,
+        "End" => KeyCode::End,
+        "PageUp" => KeyCode::PageUp,
+        "PageDown" => KeyCode::PageDown,
+        _ => KeyCode::F(name.strip_prefix('F')?.parse().ok()?),
+    };
+    Some(code)
+}
+
+pub fn key_name(code: KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("F{n}"),
+        code => format!("{code:?}"),
+    }
+}
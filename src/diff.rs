@@ -0,0 +1,214 @@
+use ratatui::style::Color;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::{search::scalar_text, JsonPointer};
+
+pub enum DiffNode {
+    OnlyLeft,
+    OnlyRight,
+    Same,
+    Different(Value, Value),
+    Recurse(DiffTree),
+}
+
+/// Differences between children of two containers, by child
+#[derive(Default)]
+pub struct DiffTree {
+    pub children: Vec<(JsonPointer, DiffNode)>,
+}
+
+/// How a node of one side is shown in diff mode
+pub struct DiffMark {
+    pub color: Color,
+    /// Value of the other side when it is a scalar
+    pub other: Option<String>,
+}
+
+/// Compares objects by key and arrays by index, containers of different kinds have nothing in common
+pub fn json_diff(a: &Value, b: &Value) -> DiffTree {
+    let mut children = Vec::new();
+    match (a, b) {
+        (Value::Object(left), Value::Object(right)) => {
+            for (key, value) in left {
+                let node = match right.get(key) {
+                    Some(other) => diff_node(value, other),
+                    None => DiffNode::OnlyLeft,
+                };
+                children.push((JsonPointer::ObjectKey(key.clone()), node));
+            }
+            for key in right.keys().filter(|key| !left.contains_key(*key)) {
+                children.push((JsonPointer::ObjectKey(key.clone()), DiffNode::OnlyRight));
+            }
+        }
+        (Value::Array(left), Value::Array(right)) => {
+            for index in 0..left.len().max(right.len()) {
+                let node = match (left.get(index), right.get(index)) {
+                    (Some(value), Some(other)) => diff_node(value, other),
+                    (Some(_), None) => DiffNode::OnlyLeft,
+                    _ => DiffNode::OnlyRight,
+                };
+                children.push((JsonPointer::ArrayIdx(index), node));
+            }
+        }
+        _ => {
+            children.extend(child_pointers(a).map(|pointer| (pointer, DiffNode::OnlyLeft)));
+            children.extend(child_pointers(b).map(|pointer| (pointer, DiffNode::OnlyRight)));
+        }
+    }
+    DiffTree { children }
+}
+
+fn diff_node(a: &Value, b: &Value) -> DiffNode {
+    match (a, b) {
+        _ if a == b => DiffNode::Same,
+        (Value::Object(_), Value::Object(_)) | (Value::Array(_), Value::Array(_)) => {
+            DiffNode::Recurse(json_diff(a, b))
+        }
+        _ => DiffNode::Different(a.clone(), b.clone()),
+    }
+}
+
+fn child_pointers(value: &Value) -> Box<dyn Iterator<Item = JsonPointer> + '_> {
+    match value {
+        Value::Object(object) => Box::new(object.keys().cloned().map(JsonPointer::ObjectKey)),
+        Value::Array(array) => Box::new((0..array.len()).map(JsonPointer::ArrayIdx)),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+impl DiffTree {
+    /// Marks of the changed nodes of the left (or right) document; containers with
+    /// changes inside are marked as different, so they can be found while collapsed
+    pub fn marks(&self, left: bool) -> HashMap<Vec<JsonPointer>, DiffMark> {
+        let mut marks = HashMap::new();
+        self.collect_marks(left, &mut Vec::new(), &mut marks);
+        marks
+    }
+
+    fn collect_marks(
+        &self,
+        left: bool,
+        path: &mut Vec<JsonPointer>,
+        marks: &mut HashMap<Vec<JsonPointer>, DiffMark>,
+    ) {
+        for (pointer, node) in &self.children {
+            path.push(pointer.clone());
+            let mark = match node {
+                DiffNode::Same => None,
+                DiffNode::OnlyLeft if left => Some(DiffMark {
+                    color: Color::Red,
+                    other: None,
+                }),
+                DiffNode::OnlyRight if !left => Some(DiffMark {
+                    color: Color::Green,
+                    other: None,
+                }),
+                // the node is not in this document
+                DiffNode::OnlyLeft | DiffNode::OnlyRight => None,
+                DiffNode::Different(a, b) => Some(DiffMark {
+                    color: Color::Yellow,
+                    other: scalar_text(if left { b } else { a }),
+                }),
+                DiffNode::Recurse(tree) => {
+                    tree.collect_marks(left, path, marks);
+                    Some(DiffMark {
+                        color: Color::Yellow,
+                        other: None,
+                    })
+                }
+            };
+            if let Some(mark) = mark {
+                marks.insert(path.clone(), mark);
+            }
+            path.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Marks of one side as (slash-separated path, color, other value), sorted by path
+    fn marks(a: Value, b: Value, left: bool) -> Vec<(String, Color, Option<String>)> {
+        let mut marks: Vec<_> = json_diff(&a, &b)
+            .marks(left)
+            .into_iter()
+            .map(|(path, mark)| {
+                let path: Vec<String> = path
+                    .iter()
+                    .map(|pointer| match pointer {
+                        JsonPointer::ObjectKey(key) => key.clone(),
+                        JsonPointer::ArrayIdx(index) => index.to_string(),
+                        other => format!("{other:?}"),
+                    })
+                    .collect();
+                (path.join("/"), mark.color, mark.other)
+            })
+            .collect();
+        marks.sort_by(|a, b| a.0.cmp(&b.0));
+        marks
+    }
+
+    #[test]
+    fn marks_added_removed_and_changed_keys() {
+        let a = json!({"same": 1, "gone": 2, "changed": "x", "nested": {"n": 1}});
+        let b = json!({"same": 1, "changed": "y", "nested": {"n": 2}, "new": true});
+        assert_eq!(
+            marks(a.clone(), b.clone(), true),
+            [
+                ("changed".into(), Color::Yellow, Some("y".into())),
+                ("gone".into(), Color::Red, None),
+                ("nested".into(), Color::Yellow, None),
+                ("nested/n".into(), Color::Yellow, Some("2".into())),
+            ]
+        );
+        assert_eq!(
+            marks(a, b, false),
+            [
+                ("changed".into(), Color::Yellow, Some("x".into())),
+                ("nested".into(), Color::Yellow, None),
+                ("nested/n".into(), Color::Yellow, Some("1".into())),
+                ("new".into(), Color::Green, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn marks_array_length_changes_by_index() {
+        let a = json!([1, 2, 3]);
+        let b = json!([1, 5]);
+        assert_eq!(
+            marks(a.clone(), b.clone(), true),
+            [
+                ("1".into(), Color::Yellow, Some("5".into())),
+                ("2".into(), Color::Red, None),
+            ]
+        );
+        assert_eq!(
+            marks(a, b, false),
+            [("1".into(), Color::Yellow, Some("2".into()))]
+        );
+    }
+
+    #[test]
+    fn marks_type_changes_as_different() {
+        let a = json!({"v": [1], "n": 1, "o": {"k": 1}});
+        let b = json!({"v": {"0": 1}, "n": "1", "o": null});
+        assert_eq!(
+            marks(a, b, true),
+            [
+                ("n".into(), Color::Yellow, Some("1".into())),
+                ("o".into(), Color::Yellow, Some("null".into())),
+                ("v".into(), Color::Yellow, None),
+            ]
+        );
+        // root containers of different kinds share no children
+        assert_eq!(
+            marks(json!([1]), json!({"0": 1}), true),
+            [("0".into(), Color::Red, None)]
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use serde_json::Value;
+use std::{
+    io::{self, Write},
+    process::{Command, Output, Stdio},
+    thread,
+};
+
+/// Runs jq `program` on the document with the `jq` binary. A program with several outputs
+/// gives an array of them, errors are jq's own messages.
+pub fn run_jq(root: &Value, program: &str) -> Result<Value, String> {
+    let mut command = Command::new("jq");
+    command.args(["--compact-output", program]);
+    let input = serde_json::to_vec(root).map_err(|err| err.to_string())?;
+    let output = run_with_input(command, input).map_err(|err| match err.kind() {
+        io::ErrorKind::NotFound => "jq is not installed, filters need it in PATH".to_string(),
+        _ => format!("jq: {err}"),
+    })?;
+    if !output.status.success() {
+        return Err(failure(&output, "jq"));
+    }
+    let mut outputs = serde_json::Deserializer::from_slice(&output.stdout)
+        .into_iter::<Value>()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("jq output: {err}"))?;
+    match outputs.len() {
+        1 => Ok(outputs.remove(0)),
+        _ => Ok(Value::Array(outputs)),
+    }
+}
+
+/// Pipes the value as pretty-printed JSON to a shell command, returning what it printed.
+/// A command exiting with an error gives its stderr.
+pub fn pipe_to_command(value: &Value, command_line: &str) -> Result<String, String> {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C");
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c");
+        command
+    };
+    command.arg(command_line);
+    let mut input = serde_json::to_vec_pretty(value).map_err(|err| err.to_string())?;
+    input.push(b'\n');
+    let output = run_with_input(command, input).map_err(|err| format!("{command_line}: {err}"))?;
+    if !output.status.success() {
+        return Err(failure(&output, "command"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Runs the command with `input` on its stdin, collecting stdout and stderr
+fn run_with_input(mut command: Command, input: Vec<u8>) -> io::Result<Output> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    // written aside, the command may fill its output pipe before reading all of the input
+    let writer = thread::spawn(move || {
+        // a broken pipe only means the command stopped early, its exit status tells why
+        let _ = stdin.write_all(&input);
+    });
+    let output = child.wait_with_output();
+    let _ = writer.join();
+    output
+}
+
+/// Stderr of a failed command, or its exit status when it printed nothing
+fn failure(output: &Output, program: &str) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match stderr.trim() {
+        "" => format!("{program} failed with {}", output.status),
+        message => message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn filters_with_jq() {
+        if Command::new("jq").arg("--version").output().is_err() {
+            return;
+        }
+        let root = json!({"items": [{"id": 1, "total": 5, "x": 0}, {"id": 2, "total": 7}]});
+        assert_eq!(
+            run_jq(&root, ".items | map({id, total})").unwrap(),
+            json!([{"id": 1, "total": 5}, {"id": 2, "total": 7}])
+        );
+        assert_eq!(run_jq(&root, ".items[].id").unwrap(), json!([1, 2]));
+        assert_eq!(run_jq(&root, "empty").unwrap(), json!([]));
+        let err = run_jq(&root, ".items | map(").unwrap_err();
+        assert!(err.contains("error"), "{err}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pipes_value_to_command() {
+        let value = json!({"name": "Ann"});
+        assert_eq!(
+            pipe_to_command(&value, "cat").unwrap(),
+            "{\n  \"name\": \"Ann\"\n}\n"
+        );
+        assert_eq!(pipe_to_command(&value, "grep -c name").unwrap(), "1\n");
+        assert_eq!(
+            pipe_to_command(&value, "echo oops >&2; exit 3").unwrap_err(),
+            "oops"
+        );
+        let err = pipe_to_command(&value, "exit 2").unwrap_err();
+        assert!(err.starts_with("command failed with"), "{err}");
+    }
+}
@@ -0,0 +1,126 @@
+use serde_json::Value;
+
+use crate::{search::fuzzy_score, JsonPointer};
+
+/// Paths indexed at most, the rest of a huge document can't be found
+pub const MAX_INDEXED_PATHS: usize = 200_000;
+/// Best results listed
+const MAX_RESULTS: usize = 500;
+
+/// Dotted paths of all nodes (`ticket.state.list.0.customer_id`), in tree order
+pub struct PathIndex {
+    entries: Vec<(String, Vec<JsonPointer>)>,
+    /// More nodes than `MAX_INDEXED_PATHS`
+    pub truncated: bool,
+}
+
+impl PathIndex {
+    pub fn build(root: &Value, limit: usize) -> Self {
+        let mut index = Self {
+            entries: Vec::new(),
+            truncated: false,
+        };
+        index.add_children(root, &mut Vec::new(), "", limit);
+        index
+    }
+
+    fn add_children(
+        &mut self,
+        value: &Value,
+        path: &mut Vec<JsonPointer>,
+        text: &str,
+        limit: usize,
+    ) {
+        let children: Box<dyn Iterator<Item = (JsonPointer, &Value)>> = match value {
+            Value::Object(object) => Box::new(
+                object
+                    .iter()
+                    .map(|(key, value)| (JsonPointer::ObjectKey(key.clone()), value)),
+            ),
+            Value::Array(array) => Box::new(
+                array
+                    .iter()
+                    .enumerate()
+                    .map(|(index, value)| (JsonPointer::ArrayIdx(index), value)),
+            ),
+            _ => return,
+        };
+        for (pointer, child) in children {
+            if self.entries.len() == limit {
+                self.truncated = true;
+                return;
+            }
+            let text = match text {
+                "" => pointer.to_string(),
+                parent => format!("{parent}.{pointer}"),
+            };
+            path.push(pointer);
+            self.entries.push((text.clone(), path.clone()));
+            self.add_children(child, path, &text, limit);
+            path.pop();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn text(&self, entry: usize) -> &str {
+        &self.entries[entry].0
+    }
+
+    pub fn path(&self, entry: usize) -> &[JsonPointer] {
+        &self.entries[entry].1
+    }
+
+    /// Entries fuzzy-matching `query`, best first (shorter paths first among equal scores),
+    /// all in tree order for an empty query
+    pub fn rank(&self, query: &str) -> Vec<usize> {
+        if query.is_empty() {
+            return (0..self.entries.len().min(MAX_RESULTS)).collect();
+        }
+        let mut ranked: Vec<_> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(entry, (text, _))| Some((fuzzy_score(query, text)?, text.len(), entry)))
+            .collect();
+        ranked.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        ranked
+            .into_iter()
+            .take(MAX_RESULTS)
+            .map(|(_, _, entry)| entry)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn ranks_dotted_paths() {
+        let root = json!({"ticket": {"state": {"list": [{"customer_id": 7}]}}, "customers": []});
+        let index = PathIndex::build(&root, MAX_INDEXED_PATHS);
+        let texts: Vec<_> = (0..index.len()).map(|entry| index.text(entry)).collect();
+        assert_eq!(
+            texts,
+            [
+                "ticket",
+                "ticket.state",
+                "ticket.state.list",
+                "ticket.state.list.0",
+                "ticket.state.list.0.customer_id",
+                "customers"
+            ]
+        );
+        let ranked = index.rank("custid");
+        assert_eq!(index.text(ranked[0]), "ticket.state.list.0.customer_id");
+        assert_eq!(index.path(ranked[0]).len(), 5);
+        assert!(index.rank("zz").is_empty());
+        let capped = PathIndex::build(&root, 3);
+        assert_eq!(capped.len(), 3);
+        assert!(capped.truncated);
+    }
+}
@@ -0,0 +1,127 @@
+use std::{error::Error, fs};
+
+use crate::session::data_dir;
+
+/// Queries kept, older ones are dropped
+const MAX_HISTORY: usize = 100;
+const HISTORY_FILE: &str = "search_history.json";
+
+/// Committed search queries, oldest first, stored in `search_history.json` of the data
+/// directory and recalled with Up/Down in the search prompt
+#[derive(Default)]
+pub struct SearchHistory {
+    entries: Vec<String>,
+    /// Entry recalled last, `None` while typing a query
+    position: Option<usize>,
+    /// Query typed before recalling, given back by Down past the newest entry
+    draft: String,
+}
+
+impl SearchHistory {
+    /// Stored history, empty when the file is missing or can't be read
+    pub fn load() -> Self {
+        Self {
+            entries: read_entries().unwrap_or_default(),
+            ..Self::default()
+        }
+    }
+
+    /// Adds the query as the newest entry after taking in the ones stored meanwhile (by
+    /// other tabs), and stores the history
+    pub fn commit(&mut self, query: &str) -> Result<(), Box<dyn Error>> {
+        if query.is_empty() {
+            self.reset();
+            return Ok(());
+        }
+        if let Some(stored) = read_entries() {
+            self.entries = stored;
+        }
+        self.push(query);
+        let dir = data_dir().ok_or("no data directory")?;
+        fs::create_dir_all(&dir)?;
+        fs::write(
+            dir.join(HISTORY_FILE),
+            serde_json::to_string(&self.entries)?,
+        )?;
+        Ok(())
+    }
+
+    /// Adds the query as the newest entry, moving an equal older one
+    pub fn push(&mut self, query: &str) {
+        self.reset();
+        if query.is_empty() {
+            return;
+        }
+        self.entries.retain(|entry| entry != query);
+        self.entries.push(query.to_string());
+        if self.entries.len() > MAX_HISTORY {
+            self.entries.remove(0);
+        }
+    }
+
+    /// Entry before the recalled one, the newest when `current` is being typed
+    pub fn older(&mut self, current: &str) -> Option<String> {
+        let position = match self.position {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len() - 1
+            }
+            Some(0) => return None,
+            Some(position) => position - 1,
+        };
+        self.position = Some(position);
+        Some(self.entries[position].clone())
+    }
+
+    /// Entry after the recalled one, the query typed before recalling after the newest
+    pub fn newer(&mut self) -> Option<String> {
+        let position = self.position? + 1;
+        if position < self.entries.len() {
+            self.position = Some(position);
+            Some(self.entries[position].clone())
+        } else {
+            self.position = None;
+            Some(std::mem::take(&mut self.draft))
+        }
+    }
+
+    /// Stops recalling, an edited entry becomes a new query and the entry stays as it was
+    pub fn reset(&mut self) {
+        self.position = None;
+    }
+}
+
+fn read_entries() -> Option<Vec<String>> {
+    let text = fs::read_to_string(data_dir()?.join(HISTORY_FILE)).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recalls_queries_newest_first() {
+        let mut history = SearchHistory::default();
+        assert_eq!(history.older("typed"), None);
+        for query in ["price", "customer", "", "price"] {
+            history.push(query);
+        }
+        assert_eq!(history.older("pri").as_deref(), Some("price"));
+        assert_eq!(history.older("price").as_deref(), Some("customer"));
+        assert_eq!(history.older("customer"), None);
+        assert_eq!(history.newer().as_deref(), Some("price"));
+        assert_eq!(history.newer().as_deref(), Some("pri"));
+        assert_eq!(history.newer(), None);
+
+        history.older("");
+        history.reset();
+        assert_eq!(history.older("price-x").as_deref(), Some("price"));
+        for query in 0..MAX_HISTORY + 5 {
+            history.push(&query.to_string());
+        }
+        assert_eq!(history.entries.len(), MAX_HISTORY);
+        assert_eq!(history.entries[0], "5");
+    }
+}
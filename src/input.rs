@@ -0,0 +1,1415 @@
+use base64::prelude::*;
+use flate2::read::{MultiGzDecoder, ZlibDecoder};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    error::Error,
+    fmt, fs, io,
+    io::Read,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::location::MAX_LOCATED_SIZE;
+use crate::nested::parse_embedded;
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+/// Input size limit unless set with `--max-size`
+pub const DEFAULT_MAX_SIZE: u64 = 1 << 30;
+
+#[derive(Clone)]
+pub enum InputSource {
+    Stdin,
+    File(PathBuf),
+    Url { url: String, headers: Vec<String> },
+    Inline(String),
+    Clipboard,
+}
+
+impl InputSource {
+    /// Format implied by the file extension, if any
+    pub fn format(&self) -> Option<InputFormat> {
+        match self {
+            Self::File(path) => InputFormat::from_extension(path),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for InputSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stdin => write!(f, "(stdin)"),
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::Url { url, .. } => write!(f, "{url}"),
+            Self::Inline(_) => write!(f, "--json"),
+            Self::Clipboard => write!(f, "(clipboard)"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InputFormat {
+    Json,
+    NdJson,
+    Jsonc,
+    Json5,
+    Yaml,
+    Toml,
+    Csv,
+    MsgPack,
+    Cbor,
+    Bson,
+    Har,
+}
+
+impl InputFormat {
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "gz" | "zz" | "zst" => Self::from_extension(Path::new(path.file_stem()?)),
+            "json" => Some(Self::Json),
+            "jsonl" | "ndjson" => Some(Self::NdJson),
+            "jsonc" => Some(Self::Jsonc),
+            "json5" => Some(Self::Json5),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            "csv" => Some(Self::Csv),
+            "msgpack" | "mpk" => Some(Self::MsgPack),
+            "cbor" => Some(Self::Cbor),
+            "bson" => Some(Self::Bson),
+            "har" => Some(Self::Har),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct InputOptions {
+    pub format: Option<InputFormat>,
+    pub gzip: bool,
+    pub strict: bool,
+    /// Retry JSON failing to parse with trailing commas removed
+    pub lenient: bool,
+    /// CSV field delimiter, `,` when not set
+    pub delimiter: Option<u8>,
+    /// Parse numeric-looking CSV cells into numbers
+    pub csv_numbers: bool,
+    /// Show binary blobs as base64 instead of their size (MessagePack) or hex (CBOR)
+    pub bytes_base64: bool,
+    /// Show BSON as canonical extended JSON instead of readable strings
+    pub extended_json: bool,
+    /// Expand JSON embedded in string values into child nodes (done by the viewer)
+    pub parse_nested: bool,
+    /// Bytes of input read before asking whether to go on, no limit when not set
+    pub max_size: Option<u64>,
+}
+
+/// Stage of loading reported while the input is read and parsed
+#[derive(Clone, Copy)]
+pub enum Progress {
+    /// Bytes read so far
+    Read(usize),
+    Parsing,
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(bytes) => write!(f, "reading… {} MB", bytes >> 20),
+            Self::Parsing => write!(f, "parsing…"),
+        }
+    }
+}
+
+/// Input bigger than the size limit
+#[derive(Debug)]
+pub struct TooLarge {
+    pub max_size: u64,
+}
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "input is larger than {}, raise the limit with --max-size (0 for none)",
+            format_size(self.max_size)
+        )
+    }
+}
+
+/// Size in the biggest unit it is a whole number of, e.g. `1 GB` or `1536 KB`
+pub fn format_size(bytes: u64) -> String {
+    for (shift, unit) in [(30, "GB"), (20, "MB"), (10, "KB")] {
+        if bytes >= 1 << shift && bytes.is_multiple_of(1 << shift) {
+            return format!("{} {unit}", bytes >> shift);
+        }
+    }
+    format!("{bytes} bytes")
+}
+
+impl Error for TooLarge {}
+
+/// Checks the input size while reading, `allow_larger` is asked (given the limit)
+/// once the input turns out bigger
+struct SizeLimit<'a> {
+    max_size: Option<u64>,
+    allow_larger: &'a dyn Fn(u64) -> bool,
+}
+
+impl SizeLimit<'_> {
+    fn check(&mut self, size: u64) -> Result<(), TooLarge> {
+        match self.max_size {
+            Some(max_size) if size > max_size => {
+                if !(self.allow_larger)(max_size) {
+                    return Err(TooLarge { max_size });
+                }
+                self.max_size = None;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Parsed input
+#[derive(Debug)]
+pub struct Document {
+    pub value: Value,
+    /// How invalid input was recovered, e.g. comments stripped
+    pub notice: Option<String>,
+    /// JSON source text, kept to show where values are in it
+    pub source: Option<String>,
+}
+
+impl From<Value> for Document {
+    fn from(value: Value) -> Self {
+        Self {
+            value,
+            notice: None,
+            source: None,
+        }
+    }
+}
+
+/// Reads and parses the source, format given in options wins over the file extension.
+/// Input over the size limit is loaded only when `allow_larger` agrees.
+pub fn load_input(
+    source: &InputSource,
+    options: &InputOptions,
+    progress: &dyn Fn(Progress),
+    allow_larger: &dyn Fn(u64) -> bool,
+) -> Result<Document, Box<dyn Error>> {
+    let format = options.format.or_else(|| source.format());
+    let mut limit = SizeLimit {
+        max_size: options.max_size,
+        allow_larger,
+    };
+    let raw = read_input(source.clone(), options.gzip, &mut limit, progress)?;
+    progress(Progress::Parsing);
+    let document = parse_bytes(raw, format, options);
+    match source {
+        InputSource::Inline(input) => document.map_err(|err| with_snippet(input, err)),
+        _ => document,
+    }
+}
+
+const SNIPPET_CONTEXT: usize = 30;
+
+/// Appends the part of the line a JSON error points to, with a caret under the column
+fn with_snippet(input: &str, err: Box<dyn Error>) -> Box<dyn Error> {
+    let Some(json_err) = err.downcast_ref::<serde_json::Error>() else {
+        return err;
+    };
+    let Some(line) = input.lines().nth(json_err.line().saturating_sub(1)) else {
+        return err;
+    };
+    let column = json_err.column().saturating_sub(1);
+    let start = column.saturating_sub(SNIPPET_CONTEXT);
+    let snippet: String = line.chars().skip(start).take(2 * SNIPPET_CONTEXT).collect();
+    format!("{err}\n  {snippet}\n  {:>1$}", "^", column - start + 1).into()
+}
+
+/// Decodes and parses raw input, errors mention the byte order mark if there was one.
+/// JSON failing to parse is retried as JSONC, and without trailing commas or as JSON5 when lenient.
+pub fn parse_bytes(
+    raw: Vec<u8>,
+    format: Option<InputFormat>,
+    options: &InputOptions,
+) -> Result<Document, Box<dyn Error>> {
+    if raw.is_empty() {
+        return Err("empty input".into());
+    }
+    let value = match format {
+        Some(InputFormat::MsgPack) => parse_msgpack(&raw, options.bytes_base64),
+        Some(InputFormat::Cbor) => parse_cbor(&raw, options.bytes_base64),
+        Some(InputFormat::Bson) => parse_bson(&raw, options),
+        _ => {
+            let (input, bom) = decode_text(raw)?;
+            let json = matches!(
+                format,
+                None | Some(InputFormat::Json) | Some(InputFormat::Jsonc)
+            );
+            return match parse_input(&input, format, options) {
+                Ok(value) => Ok(value.into()),
+                Err(err) if json => recover(&input, options).ok_or(err),
+                Err(err) => Err(err),
+            }
+            .map(|mut document| {
+                // positions hold in JSON and JSONC, comments and trailing commas are skipped
+                if (json || format == Some(InputFormat::NdJson)) && input.len() <= MAX_LOCATED_SIZE
+                {
+                    document.source = Some(input);
+                }
+                document
+            })
+            .map_err(|err| match bom {
+                Some(bom) => {
+                    format!("{err} (input starts with {bom}, stripped before parsing)").into()
+                }
+                None => err,
+            });
+        }
+    };
+    value.map(Document::from)
+}
+
+/// Retries invalid JSON with comments stripped, and trailing commas removed when lenient,
+/// lenient then falls back to JSON5
+fn recover(input: &str, options: &InputOptions) -> Option<Document> {
+    let mut fixes = Vec::new();
+    let mut text = strip_comments(input);
+    if text != input {
+        fixes.push("comments stripped");
+    }
+    if options.lenient {
+        let stripped = strip_trailing_commas(&text);
+        if stripped != text {
+            fixes.push("lenient: trailing commas removed");
+            text = stripped;
+        }
+    }
+    let value = match parse_input(&text, None, options) {
+        Ok(value) if !fixes.is_empty() => value,
+        // single-quoted strings and unquoted keys, only reached by input that isn't valid JSON
+        _ if options.lenient => {
+            fixes = vec!["lenient: parsed as JSON5"];
+            json5::from_str(input).ok()?
+        }
+        _ => return None,
+    };
+    Some(Document {
+        value,
+        notice: Some(fixes.join(", ")),
+        source: None,
+    })
+}
+
+/// Blanks out commas followed only by whitespace up to `}` or `]`, outside of strings
+pub fn strip_trailing_commas(input: &str) -> String {
+    const WHITESPACE: [char; 4] = [' ', '\t', '\n', '\r'];
+    let mut output = String::with_capacity(input.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, c) in input.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ','
+            && input[index + 1..]
+                .trim_start_matches(WHITESPACE)
+                .starts_with(['}', ']'])
+        {
+            output.push(' ');
+            continue;
+        }
+        output.push(c);
+    }
+    output
+}
+
+/// Blanks out `//` and `/* */` comments outside of strings with spaces,
+/// so positions in errors still match the original input
+pub fn strip_comments(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    let blank = |output: &mut String, c: char| match c {
+        '\n' | '\r' => output.push(c),
+        _ => output.extend(std::iter::repeat_n(' ', c.len_utf8())),
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                output.push(c);
+                while let Some(c) = chars.next() {
+                    output.push(c);
+                    match c {
+                        '\\' => output.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                blank(&mut output, c);
+                while let Some(c) = chars.next_if(|c| *c != '\n') {
+                    blank(&mut output, c);
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                blank(&mut output, c);
+                blank(&mut output, chars.next().unwrap_or_default());
+                while let Some(c) = chars.next() {
+                    blank(&mut output, c);
+                    if c == '*' && chars.peek() == Some(&'/') {
+                        blank(&mut output, chars.next().unwrap_or_default());
+                        break;
+                    }
+                }
+            }
+            _ => output.push(c),
+        }
+    }
+    output
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bom {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl fmt::Display for Bom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Utf8 => write!(f, "UTF-8 BOM"),
+            Self::Utf16Le => write!(f, "UTF-16 LE BOM"),
+            Self::Utf16Be => write!(f, "UTF-16 BE BOM"),
+        }
+    }
+}
+
+/// Decodes UTF-8 text, stripping a byte order mark and transcoding UTF-16 when it has one
+pub fn decode_text(raw: Vec<u8>) -> Result<(String, Option<Bom>), Box<dyn Error>> {
+    if let Some(text) = raw.strip_prefix(&[0xef, 0xbb, 0xbf]) {
+        return Ok((String::from_utf8(text.to_vec())?, Some(Bom::Utf8)));
+    }
+    let (bom, decode): (Bom, fn([u8; 2]) -> u16) = match raw.get(..2) {
+        Some([0xff, 0xfe]) => (Bom::Utf16Le, u16::from_le_bytes),
+        Some([0xfe, 0xff]) => (Bom::Utf16Be, u16::from_be_bytes),
+        _ => return Ok((String::from_utf8(raw)?, None)),
+    };
+    let pairs = raw[2..].chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err(format!("{bom}: odd number of bytes").into());
+    }
+    let units: Vec<u16> = pairs.map(|pair| decode([pair[0], pair[1]])).collect();
+    let text = String::from_utf16(&units).map_err(|err| format!("{bom}: {err}"))?;
+    Ok((text, Some(bom)))
+}
+
+/// ID bytes and the deflate method, binary formats could start with the ID bytes alone
+const GZIP_MAGIC: [u8; 3] = [0x1f, 0x8b, 0x08];
+/// Deflate with the default compression level
+const ZLIB_MAGIC: [u8; 2] = [0x78, 0x9c];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Reads the whole input, transparently decompressing it (always as gzip when `gzip`
+/// is forced or the file has `.gz` extension)
+fn read_input(
+    source: InputSource,
+    gzip: bool,
+    limit: &mut SizeLimit,
+    progress: &dyn Fn(Progress),
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let gzip = gzip
+        || matches!(&source, InputSource::File(path) if path.extension().is_some_and(|ext| ext == "gz"));
+    let raw = match source {
+        InputSource::Stdin => read_all(io::stdin(), limit, progress)?,
+        InputSource::File(path) => {
+            let file = fs::File::open(path)?;
+            limit.check(file.metadata()?.len())?;
+            read_all(file, limit, progress)?
+        }
+        InputSource::Url { url, headers } => {
+            let mut response = fetch_url(&url, &headers)?;
+            let body = response.body_mut().with_config().limit(u64::MAX).reader();
+            read_all(body, limit, progress)?
+        }
+        InputSource::Inline(input) => input.into_bytes(),
+        InputSource::Clipboard => {
+            let text = read_clipboard()?;
+            limit.check(text.len() as u64)?;
+            text.into_bytes()
+        }
+    };
+    if gzip {
+        gunzip(&raw)
+    } else {
+        decompress_if_needed(raw)
+    }
+}
+
+/// Text of the system clipboard, an error rather than a panic when there is none
+/// (e.g. no display server)
+fn read_clipboard() -> Result<String, Box<dyn Error>> {
+    let mut clipboard =
+        arboard::Clipboard::new().map_err(|err| format!("clipboard is not available ({err})"))?;
+    match clipboard.get_text() {
+        Ok(text) => Ok(text),
+        Err(arboard::Error::ContentNotAvailable) => Err("clipboard holds no text".into()),
+        Err(err) => Err(format!("clipboard: {err}").into()),
+    }
+}
+
+/// Decompresses gzip, zlib or zstd input recognized by its magic bytes, other input is
+/// returned as is
+pub fn decompress_if_needed(raw: Vec<u8>) -> Result<Vec<u8>, Box<dyn Error>> {
+    if raw.starts_with(&GZIP_MAGIC) {
+        gunzip(&raw)
+    } else if raw.starts_with(&ZSTD_MAGIC) {
+        unzstd(&raw)
+    } else if raw.starts_with(&ZLIB_MAGIC) {
+        // a CBOR text string of 156 bytes starts the same way, kept when it isn't zlib
+        let mut buff = Vec::new();
+        match ZlibDecoder::new(raw.as_slice()).read_to_end(&mut buff) {
+            Ok(_) => Ok(buff),
+            Err(_) => Ok(raw),
+        }
+    } else {
+        Ok(raw)
+    }
+}
+
+const READ_CHUNK: usize = 64 * 1024;
+/// Bytes read between progress reports
+const PROGRESS_STEP: usize = 1 << 20;
+
+fn read_all(
+    mut reader: impl Read,
+    limit: &mut SizeLimit,
+    progress: &dyn Fn(Progress),
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut buff = Vec::new();
+    let mut chunk = vec![0; READ_CHUNK];
+    let mut reported = 0;
+    loop {
+        let count = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(count) => count,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        };
+        buff.extend_from_slice(&chunk[..count]);
+        limit.check(buff.len() as u64)?;
+        if buff.len() - reported >= PROGRESS_STEP {
+            reported = buff.len();
+            progress(Progress::Read(reported));
+        }
+    }
+    Ok(buff)
+}
+
+fn gunzip(raw: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut buff = Vec::new();
+    MultiGzDecoder::new(raw)
+        .read_to_end(&mut buff)
+        .map_err(|err| format!("gzip: truncated or corrupt archive ({err})"))?;
+    Ok(buff)
+}
+
+/// Decompresses frame by frame, so that a corrupt one can be told; frames are concatenated
+#[cfg(feature = "zstd")]
+fn unzstd(raw: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut buff = Vec::new();
+    let mut rest = raw;
+    let mut index = 0;
+    while !rest.is_empty() {
+        let size = zstd::zstd_safe::find_frame_compressed_size(rest).map_err(|code| {
+            let reason = zstd::zstd_safe::get_error_name(code);
+            format!("zstd: frame {index}: truncated or corrupt ({reason})")
+        })?;
+        let (frame, next) = rest.split_at(size);
+        let data = zstd::decode_all(frame)
+            .map_err(|err| format!("zstd: frame {index}: corrupt ({err})"))?;
+        buff.extend(data);
+        rest = next;
+        index += 1;
+    }
+    Ok(buff)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn unzstd(_raw: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    Err("zstd: not supported by this build (enable the zstd feature)".into())
+}
+
+pub fn is_url(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+fn fetch_url(
+    url: &str,
+    headers: &[String],
+) -> Result<ureq::http::Response<ureq::Body>, Box<dyn Error>> {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(HTTP_TIMEOUT))
+        .build()
+        .into();
+    let mut request = agent.get(url).header("Accept", "application/json");
+    for header in headers {
+        let (name, value) = header
+            .split_once(':')
+            .ok_or_else(|| format!("invalid header '{header}', expected 'Name: value'"))?;
+        request = request.header(name.trim(), value.trim());
+    }
+    Ok(request.call()?)
+}
+
+/// JSON Lines when there are several non-blank lines and the first one is a complete JSON value,
+/// otherwise a (possibly concatenated) JSON document
+pub fn detect_format(input: &str) -> InputFormat {
+    let mut lines = input.lines().filter(|line| !line.trim().is_empty());
+    match (lines.next(), lines.next()) {
+        (Some(first), Some(_)) if serde_json::from_str::<Value>(first).is_ok() => {
+            InputFormat::NdJson
+        }
+        _ => InputFormat::Json,
+    }
+}
+
+/// Parses JSON Lines into a virtual top-level array, one element per non-blank line.
+/// Malformed lines fail the whole input when `strict`, otherwise they become error leaves.
+pub fn parse_ndjson(input: &str, strict: bool) -> Result<Value, Box<dyn Error>> {
+    let mut records = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(value) => records.push(value),
+            Err(err) if strict => return Err(format!("line {}: {err}", index + 1).into()),
+            Err(err) => records.push(Value::String(format!("<line {}: {err}>", index + 1))),
+        }
+    }
+    Ok(Value::Array(records))
+}
+
+/// Parses input in given format, or as strict JSON (falling back to JSON Lines) when not known
+pub fn parse_input(
+    input: &str,
+    format: Option<InputFormat>,
+    options: &InputOptions,
+) -> Result<Value, Box<dyn Error>> {
+    let strict = options.strict;
+    match format {
+        Some(InputFormat::Json) => parse_json_stream(input),
+        Some(InputFormat::NdJson) => parse_ndjson(input, strict),
+        Some(InputFormat::Jsonc) => parse_json_stream(&strip_comments(input)),
+        // json5 has no value type of its own, it deserializes straight into serde_json's
+        Some(InputFormat::Json5) => {
+            json5::from_str(input).map_err(|err| format!("json5: {err}").into())
+        }
+        Some(InputFormat::Yaml) => yaml_to_json(input),
+        Some(InputFormat::Toml) => parse_toml(input),
+        Some(InputFormat::Csv) => parse_csv(input, options.delimiter, options.csv_numbers),
+        Some(InputFormat::MsgPack) => parse_msgpack(input.as_bytes(), options.bytes_base64),
+        Some(InputFormat::Cbor) => parse_cbor(input.as_bytes(), options.bytes_base64),
+        Some(InputFormat::Bson) => parse_bson(input.as_bytes(), options),
+        Some(InputFormat::Har) => parse_har(input),
+        None => match parse_json_stream(input) {
+            Ok(value) => Ok(value),
+            Err(_) if detect_format(input) == InputFormat::NdJson => parse_ndjson(input, strict),
+            Err(err) => Err(err),
+        },
+    }
+}
+
+/// Parses one or more concatenated JSON documents, several of them become a top-level array
+pub fn parse_json_stream(input: &str) -> Result<Value, Box<dyn Error>> {
+    let mut stream = serde_json::Deserializer::from_str(input).into_iter::<Value>();
+    let mut documents = Vec::new();
+    loop {
+        let rest = &input[stream.byte_offset()..];
+        let offset = input.len() - rest.trim_start().len();
+        match stream.next() {
+            Some(Ok(value)) => documents.push(value),
+            Some(Err(err)) if documents.is_empty() => return Err(err.into()),
+            Some(Err(err)) => {
+                let number = documents.len() + 1;
+                return Err(format!("document {number} at byte {offset}: {err}").into());
+            }
+            None => break,
+        }
+    }
+    match documents.len() {
+        // whitespace only
+        0 => Err("empty input".into()),
+        1 => Ok(documents.remove(0)),
+        _ => Ok(Value::Array(documents)),
+    }
+}
+
+/// Parses YAML with aliases and `<<` merge keys resolved; multiple documents become a top-level array
+pub fn yaml_to_json(input: &str) -> Result<Value, Box<dyn Error>> {
+    let mut documents = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(input) {
+        let mut value =
+            serde_yaml::Value::deserialize(document).map_err(|err| format!("yaml: {err}"))?;
+        value.apply_merge().map_err(|err| format!("yaml: {err}"))?;
+        documents.push(serde_json::to_value(value)?);
+    }
+    match documents.len() {
+        1 => Ok(documents.remove(0)),
+        _ => Ok(Value::Array(documents)),
+    }
+}
+
+pub fn parse_toml(input: &str) -> Result<Value, Box<dyn Error>> {
+    let table: toml::Table = toml::from_str(input)?;
+    Ok(toml_to_json(toml::Value::Table(table)))
+}
+
+/// Parses CSV with a header row into an array of objects keyed by the headers.
+/// Short rows are padded with nulls, rows with more cells than headers are an error.
+pub fn parse_csv(
+    input: &str,
+    delimiter: Option<u8>,
+    numbers: bool,
+) -> Result<Value, Box<dyn Error>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter.unwrap_or(b','))
+        .flexible(true)
+        .from_reader(input.as_bytes());
+    let headers = reader.headers()?.clone();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|err| format!("csv: {err}"))?;
+        if record.len() > headers.len() {
+            let line = record.position().map_or(0, |position| position.line());
+            return Err(format!(
+                "csv: line {line}: {} cells, but only {} headers",
+                record.len(),
+                headers.len()
+            )
+            .into());
+        }
+        let row = headers
+            .iter()
+            .enumerate()
+            .map(|(index, header)| {
+                let cell = match record.get(index) {
+                    Some(cell) if numbers => csv_number(cell),
+                    Some(cell) => Value::String(cell.to_string()),
+                    None => Value::Null,
+                };
+                (header.to_string(), cell)
+            })
+            .collect();
+        rows.push(Value::Object(row));
+    }
+    Ok(Value::Array(rows))
+}
+
+fn csv_number(cell: &str) -> Value {
+    if let Ok(number) = cell.parse::<i64>() {
+        return Value::from(number);
+    }
+    match cell.parse::<f64>() {
+        Ok(number) if number.is_finite() => Value::from(number),
+        _ => Value::String(cell.to_string()),
+    }
+}
+
+pub fn parse_msgpack(raw: &[u8], bytes_base64: bool) -> Result<Value, Box<dyn Error>> {
+    let value: rmpv::Value = rmp_serde::from_slice(raw).map_err(|err| format!("msgpack: {err}"))?;
+    Ok(msgpack_to_json(value, bytes_base64))
+}
+
+/// Converts MessagePack value. Binary blobs are summarized as `<bytes: N>` (or base64),
+/// non-string map keys become their JSON text, e.g. `1`, `true` or `[1,2]`.
+pub fn msgpack_to_json(value: rmpv::Value, bytes_base64: bool) -> Value {
+    match value {
+        rmpv::Value::Nil => Value::Null,
+        rmpv::Value::Boolean(flag) => Value::Bool(flag),
+        rmpv::Value::Integer(number) => match number.as_i64() {
+            Some(number) => Value::from(number),
+            None => Value::from(number.as_u64()),
+        },
+        rmpv::Value::F32(number) => Value::from(number),
+        rmpv::Value::F64(number) => Value::from(number),
+        rmpv::Value::String(text) => match text.into_str() {
+            Some(text) => Value::String(text),
+            None => Value::String("<invalid utf-8>".to_string()),
+        },
+        rmpv::Value::Binary(bytes) if bytes_base64 => Value::String(BASE64_STANDARD.encode(bytes)),
+        rmpv::Value::Binary(bytes) => Value::String(format!("<bytes: {}>", bytes.len())),
+        rmpv::Value::Array(array) => Value::Array(
+            array
+                .into_iter()
+                .map(|value| msgpack_to_json(value, bytes_base64))
+                .collect(),
+        ),
+        rmpv::Value::Map(pairs) => Value::Object(
+            pairs
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = match msgpack_to_json(key, bytes_base64) {
+                        Value::String(key) => key,
+                        key => key.to_string(),
+                    };
+                    (key, msgpack_to_json(value, bytes_base64))
+                })
+                .collect(),
+        ),
+        rmpv::Value::Ext(tag, data) => Value::String(format!("<ext {tag}: {} bytes>", data.len())),
+    }
+}
+
+pub fn parse_cbor(raw: &[u8], bytes_base64: bool) -> Result<Value, Box<dyn Error>> {
+    let value: ciborium::Value =
+        ciborium::from_reader(raw).map_err(|err| format!("cbor: {err}"))?;
+    Ok(cbor_to_json(value, bytes_base64))
+}
+
+/// Converts CBOR value using diagnostic notation where JSON has no equivalent:
+/// byte strings become `h'0102'` (or base64), tagged scalars `1(1700000000)`,
+/// tagged arrays and maps an object with a single `<tag N>` key.
+/// Non-string map keys become their JSON text.
+pub fn cbor_to_json(value: ciborium::Value, bytes_base64: bool) -> Value {
+    match value {
+        ciborium::Value::Null => Value::Null,
+        ciborium::Value::Bool(flag) => Value::Bool(flag),
+        ciborium::Value::Integer(number) => {
+            let number = i128::from(number);
+            match (i64::try_from(number), u64::try_from(number)) {
+                (Ok(number), _) => Value::from(number),
+                (_, Ok(number)) => Value::from(number),
+                _ => Value::String(number.to_string()),
+            }
+        }
+        ciborium::Value::Float(number) => Value::from(number),
+        ciborium::Value::Text(text) => Value::String(text),
+        ciborium::Value::Bytes(bytes) if bytes_base64 => {
+            Value::String(BASE64_STANDARD.encode(bytes))
+        }
+        ciborium::Value::Bytes(bytes) => {
+            let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+            Value::String(format!("h'{hex}'"))
+        }
+        ciborium::Value::Tag(tag, value) => {
+            let hex = value.is_bytes() && !bytes_base64;
+            match cbor_to_json(*value, bytes_base64) {
+                value @ (Value::Object(_) | Value::Array(_)) => {
+                    Value::Object([(format!("<tag {tag}>"), value)].into_iter().collect())
+                }
+                Value::String(text) if hex => Value::String(format!("{tag}({text})")),
+                value => Value::String(format!("{tag}({value})")),
+            }
+        }
+        ciborium::Value::Array(array) => Value::Array(
+            array
+                .into_iter()
+                .map(|value| cbor_to_json(value, bytes_base64))
+                .collect(),
+        ),
+        ciborium::Value::Map(pairs) => Value::Object(
+            pairs
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = match cbor_to_json(key, bytes_base64) {
+                        Value::String(key) => key,
+                        key => key.to_string(),
+                    };
+                    (key, cbor_to_json(value, bytes_base64))
+                })
+                .collect(),
+        ),
+        _ => Value::Null,
+    }
+}
+
+/// Condenses HAR (browser network export) into a node per request keyed by its index,
+/// method, URL and response status. Response bodies holding JSON are parsed into child
+/// nodes, others are replaced by their mime type and size.
+pub fn parse_har(input: &str) -> Result<Value, Box<dyn Error>> {
+    let mut har: Value = serde_json::from_str(input)?;
+    let Some(Value::Array(entries)) = har.pointer_mut("/log/entries").map(Value::take) else {
+        return Err("not a HAR file, log.entries is missing".into());
+    };
+    let mut condensed = serde_json::Map::new();
+    for (index, mut entry) in entries.into_iter().enumerate() {
+        let field = |pointer| match entry.pointer(pointer) {
+            Some(Value::String(text)) => text.clone(),
+            Some(value) => value.to_string(),
+            None => "?".to_string(),
+        };
+        let key = format!(
+            "{index} {} {} {}",
+            field("/request/method"),
+            field("/request/url"),
+            field("/response/status")
+        );
+        if let Some(content) = entry.pointer_mut("/response/content") {
+            shape_har_body(content);
+        }
+        condensed.insert(key, entry);
+    }
+    Ok(Value::Object(condensed))
+}
+
+fn shape_har_body(content: &mut Value) {
+    let Some(text) = content.get("text").and_then(Value::as_str) else {
+        return;
+    };
+    let body = match content.get("encoding").and_then(Value::as_str) {
+        Some("base64") => BASE64_STANDARD.decode(text).unwrap_or_default(),
+        _ => text.as_bytes().to_vec(),
+    };
+    let shaped = match std::str::from_utf8(&body).ok().and_then(parse_embedded) {
+        Some(value) => value,
+        None => {
+            let mime = content.get("mimeType").and_then(Value::as_str);
+            Value::String(format!(
+                "<{}, {} bytes>",
+                mime.filter(|mime| !mime.is_empty())
+                    .unwrap_or("no mime type"),
+                body.len()
+            ))
+        }
+    };
+    content["text"] = shaped;
+}
+
+/// Parses one or more BSON documents (e.g. `mongodump` output), several of them become a top-level array
+pub fn parse_bson(mut raw: &[u8], options: &InputOptions) -> Result<Value, Box<dyn Error>> {
+    let mut documents = Vec::new();
+    while !raw.is_empty() {
+        let document = bson::Document::from_reader(&mut raw)
+            .map_err(|err| format!("bson: document {}: {err}", documents.len() + 1))?;
+        let document = bson::Bson::Document(document);
+        documents.push(if options.extended_json {
+            document.into_canonical_extjson()
+        } else {
+            bson_to_json(document, options.bytes_base64)
+        });
+    }
+    match documents.len() {
+        1 => Ok(documents.remove(0)),
+        _ => Ok(Value::Array(documents)),
+    }
+}
+
+/// Converts BSON value, types JSON has no equivalent for become readable strings
+/// (hex ObjectId, RFC 3339 date, `<bytes: N>` or base64 binary)
+pub fn bson_to_json(value: bson::Bson, bytes_base64: bool) -> Value {
+    use bson::Bson;
+    match value {
+        Bson::Double(number) => Value::from(number),
+        Bson::String(text) | Bson::Symbol(text) | Bson::JavaScriptCode(text) => Value::String(text),
+        Bson::Array(array) => Value::Array(
+            array
+                .into_iter()
+                .map(|value| bson_to_json(value, bytes_base64))
+                .collect(),
+        ),
+        Bson::Document(document) => Value::Object(
+            document
+                .into_iter()
+                .map(|(key, value)| (key, bson_to_json(value, bytes_base64)))
+                .collect(),
+        ),
+        Bson::Boolean(flag) => Value::Bool(flag),
+        Bson::Null | Bson::Undefined => Value::Null,
+        Bson::RegularExpression(regex) => {
+            Value::String(format!("/{}/{}", regex.pattern, regex.options))
+        }
+        Bson::JavaScriptCodeWithScope(code) => Value::String(code.code),
+        Bson::Int32(number) => Value::from(number),
+        Bson::Int64(number) => Value::from(number),
+        Bson::Timestamp(timestamp) => Value::String(format!(
+            "Timestamp({}, {})",
+            timestamp.time, timestamp.increment
+        )),
+        Bson::Binary(binary) if bytes_base64 => Value::String(BASE64_STANDARD.encode(binary.bytes)),
+        Bson::Binary(binary) => Value::String(format!("<bytes: {}>", binary.bytes.len())),
+        Bson::ObjectId(oid) => Value::String(oid.to_hex()),
+        Bson::DateTime(datetime) => Value::String(
+            datetime
+                .try_to_rfc3339_string()
+                .unwrap_or_else(|_| datetime.to_string()),
+        ),
+        Bson::Decimal128(number) => Value::String(number.to_string()),
+        Bson::MaxKey => Value::String("MaxKey".to_string()),
+        Bson::MinKey => Value::String("MinKey".to_string()),
+        value @ Bson::DbPointer(_) => value.into_relaxed_extjson(),
+    }
+}
+
+/// Converts TOML value, datetimes become strings
+pub fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(text) => Value::String(text),
+        toml::Value::Integer(number) => Value::from(number),
+        toml::Value::Float(number) => Value::from(number),
+        toml::Value::Boolean(flag) => Value::Bool(flag),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(array) => Value::Array(array.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn utf16(text: &str, bom: [u8; 2], encode: fn(u16) -> [u8; 2]) -> Vec<u8> {
+        let mut raw = bom.to_vec();
+        raw.extend(text.encode_utf16().flat_map(encode));
+        raw
+    }
+
+    #[test]
+    fn decodes_without_bom() {
+        let (text, bom) = decode_text(br#"{"a": 1}"#.to_vec()).unwrap();
+        assert_eq!(text, r#"{"a": 1}"#);
+        assert_eq!(bom, None);
+    }
+
+    #[test]
+    fn strips_utf8_bom() {
+        let raw = b"\xef\xbb\xbf{\"a\": 1}".to_vec();
+        let (text, bom) = decode_text(raw).unwrap();
+        assert_eq!(text, r#"{"a": 1}"#);
+        assert_eq!(bom, Some(Bom::Utf8));
+    }
+
+    #[test]
+    fn transcodes_utf16_le() {
+        let raw = utf16(r#"{"ä": "✓"}"#, [0xff, 0xfe], u16::to_le_bytes);
+        let (text, bom) = decode_text(raw).unwrap();
+        assert_eq!(text, r#"{"ä": "✓"}"#);
+        assert_eq!(bom, Some(Bom::Utf16Le));
+    }
+
+    #[test]
+    fn transcodes_utf16_be() {
+        let raw = utf16(r#"{"ä": "✓"}"#, [0xfe, 0xff], u16::to_be_bytes);
+        let (text, bom) = decode_text(raw).unwrap();
+        assert_eq!(text, r#"{"ä": "✓"}"#);
+        assert_eq!(bom, Some(Bom::Utf16Be));
+    }
+
+    #[test]
+    fn rejects_odd_utf16_length() {
+        let mut raw = utf16("[]", [0xff, 0xfe], u16::to_le_bytes);
+        raw.push(0);
+        assert!(decode_text(raw).is_err());
+    }
+
+    #[test]
+    fn parses_input_with_bom() {
+        let raw = utf16(r#"{"a": [1, 2]}"#, [0xfe, 0xff], u16::to_be_bytes);
+        let value = parse_bytes(raw, None, &InputOptions::default())
+            .unwrap()
+            .value;
+        assert_eq!(value, json!({"a": [1, 2]}));
+    }
+
+    #[test]
+    fn empty_input() {
+        for raw in [&b""[..], b" \n", b"\xef\xbb\xbf"] {
+            let err = parse_bytes(raw.to_vec(), None, &InputOptions::default()).unwrap_err();
+            assert!(err.to_string().starts_with("empty input"), "{err}");
+        }
+    }
+
+    #[test]
+    fn parse_error_mentions_bom() {
+        let raw = b"\xef\xbb\xbf{\"a\": }".to_vec();
+        let err = parse_bytes(raw, None, &InputOptions::default())
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("UTF-8 BOM"), "{err}");
+        let err = parse_bytes(b"{\"a\": }".to_vec(), None, &InputOptions::default())
+            .unwrap_err()
+            .to_string();
+        assert!(!err.contains("BOM"), "{err}");
+    }
+
+    #[test]
+    fn pads_short_csv_rows() {
+        let value = parse_csv("name,age\nbob,30\nann\n", None, true).unwrap();
+        assert_eq!(
+            value,
+            json!([{"name": "bob", "age": 30}, {"name": "ann", "age": null}])
+        );
+        assert!(parse_csv("a;b\n1;2;3\n", Some(b';'), false).is_err());
+    }
+
+    fn msgpack(value: rmpv::Value) -> Vec<u8> {
+        let mut raw = Vec::new();
+        rmpv::encode::write_value(&mut raw, &value).unwrap();
+        raw
+    }
+
+    #[test]
+    fn stringifies_msgpack_map_keys() {
+        let raw = msgpack(rmpv::Value::Map(vec![
+            ("name".into(), "bob".into()),
+            (1.into(), true.into()),
+            (false.into(), rmpv::Value::Nil),
+            (rmpv::Value::Nil, 2.5.into()),
+            (rmpv::Value::Array(vec![1.into(), 2.into()]), "pair".into()),
+        ]));
+        let value = parse_msgpack(&raw, false).unwrap();
+        assert_eq!(
+            value,
+            json!({"name": "bob", "1": true, "false": null, "null": 2.5, "[1,2]": "pair"})
+        );
+    }
+
+    #[test]
+    fn summarizes_msgpack_bytes() {
+        let raw = msgpack(rmpv::Value::Map(vec![(
+            "blob".into(),
+            rmpv::Value::Binary(vec![0, 1, 2, 255]),
+        )]));
+        assert_eq!(
+            parse_msgpack(&raw, false).unwrap(),
+            json!({"blob": "<bytes: 4>"})
+        );
+        assert_eq!(
+            parse_msgpack(&raw, true).unwrap(),
+            json!({"blob": "AAEC/w=="})
+        );
+    }
+
+    #[test]
+    fn parses_msgpack_bytes_as_is() {
+        // not valid UTF-8, must not go through text decoding
+        let raw = msgpack(rmpv::Value::Array(vec![(-1).into(), u64::MAX.into()]));
+        let options = InputOptions::default();
+        let value = parse_bytes(raw, Some(InputFormat::MsgPack), &options)
+            .unwrap()
+            .value;
+        assert_eq!(value, json!([-1, u64::MAX]));
+    }
+
+    #[test]
+    fn converts_cbor_tags_and_bytes() {
+        use ciborium::Value as Cbor;
+        let value = Cbor::Map(vec![
+            (Cbor::Text("raw".into()), Cbor::Bytes(vec![1, 0xff])),
+            (
+                Cbor::Integer(1.into()),
+                Cbor::Tag(0, Box::new(Cbor::Text("2024-01-01T00:00:00Z".into()))),
+            ),
+            (
+                Cbor::Text("list".into()),
+                Cbor::Tag(258, Box::new(Cbor::Array(vec![Cbor::Bool(true)]))),
+            ),
+        ]);
+        let mut raw = Vec::new();
+        ciborium::into_writer(&value, &mut raw).unwrap();
+        assert_eq!(
+            parse_cbor(&raw, false).unwrap(),
+            json!({
+                "raw": "h'01ff'",
+                "1": "0(\"2024-01-01T00:00:00Z\")",
+                "list": {"<tag 258>": [true]},
+            })
+        );
+        assert_eq!(parse_cbor(&raw, true).unwrap()["raw"], json!("Af8="));
+    }
+
+    #[test]
+    fn parses_indefinite_length_cbor() {
+        // [_ 1, {_ "a": 2}]
+        let raw = [0x9f, 0x01, 0xbf, 0x61, b'a', 0x02, 0xff, 0xff];
+        assert_eq!(parse_cbor(&raw, false).unwrap(), json!([1, {"a": 2}]));
+    }
+
+    #[test]
+    fn converts_bson_documents() {
+        let oid = bson::oid::ObjectId::parse_str("65a1b2c3d4e5f60718293a4b").unwrap();
+        let mut raw = Vec::new();
+        bson::doc! {
+            "_id": oid,
+            "at": bson::DateTime::from_millis(0),
+            "blob": bson::Binary { subtype: bson::spec::BinarySubtype::Generic, bytes: vec![1, 2, 3] },
+        }
+        .to_writer(&mut raw)
+        .unwrap();
+        bson::doc! { "n": 1_i64 }.to_writer(&mut raw).unwrap();
+        let value = parse_bson(&raw, &InputOptions::default()).unwrap();
+        assert_eq!(
+            value,
+            json!([
+                {"_id": "65a1b2c3d4e5f60718293a4b", "at": "1970-01-01T00:00:00Z", "blob": "<bytes: 3>"},
+                {"n": 1},
+            ])
+        );
+    }
+
+    #[test]
+    fn strips_comments_outside_strings() {
+        let input =
+            "{\n  // line \"comment\"\n  \"url\": \"http://x/*y*/\", /* block\n é */ \"a\": 1\n}";
+        let stripped = strip_comments(input);
+        assert_eq!(stripped.len(), input.len());
+        assert_eq!(stripped.lines().count(), input.lines().count());
+        let value: Value = serde_json::from_str(&stripped).unwrap();
+        assert_eq!(value, json!({"url": "http://x/*y*/", "a": 1}));
+    }
+
+    #[test]
+    fn falls_back_to_jsonc() {
+        let raw = b"// settings\n{\"a\": [1, 2]}".to_vec();
+        let document = parse_bytes(raw, None, &InputOptions::default()).unwrap();
+        assert_eq!(document.value, json!({"a": [1, 2]}));
+        assert!(document.notice.is_some());
+        // error positions refer to the original input
+        let raw = b"/* x */ {\"a\": }".to_vec();
+        let options = InputOptions::default();
+        let err = parse_bytes(raw, Some(InputFormat::Jsonc), &options).unwrap_err();
+        assert!(err.to_string().ends_with("line 1 column 15"), "{err}");
+    }
+
+    #[test]
+    fn strips_trailing_commas_outside_strings() {
+        let input = "{\"a\": \"x,}\", \"b\": [1, 2,\n], \"c\": \"y, ]\", \"d\": \"\\\",]\",}";
+        let stripped = strip_trailing_commas(input);
+        assert_eq!(stripped.len(), input.len());
+        let value: Value = serde_json::from_str(&stripped).unwrap();
+        assert_eq!(
+            value,
+            json!({"a": "x,}", "b": [1, 2], "c": "y, ]", "d": "\",]"})
+        );
+    }
+
+    #[test]
+    fn trailing_commas_need_lenient() {
+        let raw = b"[1, // one\n 2,\n]".to_vec();
+        assert!(parse_bytes(raw.clone(), None, &InputOptions::default()).is_err());
+        let options = InputOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let document = parse_bytes(raw, None, &options).unwrap();
+        assert_eq!(document.value, json!([1, 2]));
+        assert_eq!(
+            document.notice.as_deref(),
+            Some("comments stripped, lenient: trailing commas removed")
+        );
+    }
+
+    #[test]
+    fn parses_json5_with_error_position() {
+        let options = InputOptions::default();
+        let input = "// app\n{name: 'x', ports: [80, 443,], hex: 0x10, /* c */}";
+        let value = parse_input(input, Some(InputFormat::Json5), &options).unwrap();
+        assert_eq!(value, json!({"name": "x", "ports": [80, 443], "hex": 16}));
+        let err =
+            parse_input("{a: 1,\n  b: [1,,2]}", Some(InputFormat::Json5), &options).unwrap_err();
+        assert!(err.to_string().ends_with("line 2 column 9"), "{err}");
+    }
+
+    #[test]
+    fn yaml_resolves_aliases_and_documents() {
+        let input = "base: &b {x: 1}\nuse: *b\nmerged:\n  <<: *b\n  y: 2\n1: one\n---\na: 2\n";
+        let value = yaml_to_json(input).unwrap();
+        assert_eq!(
+            value,
+            json!([
+                {"base": {"x": 1}, "use": {"x": 1}, "merged": {"x": 1, "y": 2}, "1": "one"},
+                {"a": 2}
+            ])
+        );
+    }
+
+    #[test]
+    fn converts_toml_datetimes_to_iso_8601() {
+        let input = "[package]\nname = \"x\"\n[dates]\nodt = 1979-05-27 07:32:00-07:00\n\
+                     ldt = 1979-05-27T07:32:00.5\nday = 1979-05-27\ntime = 07:32:00\n";
+        let value = parse_toml(input).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "package": {"name": "x"},
+                "dates": {
+                    "odt": "1979-05-27T07:32:00-07:00",
+                    "ldt": "1979-05-27T07:32:00.5",
+                    "day": "1979-05-27",
+                    "time": "07:32:00"
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn lenient_reads_single_quotes_and_bare_keys() {
+        let options = InputOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let raw = b"{'a': 1, b: 'x', \"c\": [\"it's\"]}".to_vec();
+        assert!(parse_bytes(raw.clone(), None, &InputOptions::default()).is_err());
+        let document = parse_bytes(raw, None, &options).unwrap();
+        assert_eq!(document.value, json!({"a": 1, "b": "x", "c": ["it's"]}));
+        assert_eq!(document.notice.as_deref(), Some("lenient: parsed as JSON5"));
+        // valid JSON is never handed to the JSON5 parser
+        let raw = b"{\"big\": 18446744073709551615}".to_vec();
+        let document = parse_bytes(raw, None, &options).unwrap();
+        assert_eq!(document.value, json!({"big": 18446744073709551615u64}));
+        assert!(document.notice.is_none());
+    }
+
+    #[test]
+    fn decompresses_by_magic_bytes() {
+        use flate2::{write::ZlibEncoder, Compression};
+        use std::io::Write;
+        let json = br#"{"a": [1, 2]}"#.to_vec();
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&json).unwrap();
+        let zlib = encoder.finish().unwrap();
+        assert_eq!(decompress_if_needed(zlib).unwrap(), json);
+        assert_eq!(decompress_if_needed(json.clone()).unwrap(), json);
+        // CBOR text string of 156 bytes
+        let mut cbor = vec![0x78, 0x9c];
+        cbor.extend([b'a'; 156]);
+        assert_eq!(decompress_if_needed(cbor.clone()).unwrap(), cbor);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn decompresses_zstd_frames() {
+        let json = br#"{"a": [1, 2]}"#.to_vec();
+        let frame = zstd::encode_all(json.as_slice(), 0).unwrap();
+        assert_eq!(decompress_if_needed(frame.clone()).unwrap(), json);
+        let mut frames = frame.repeat(2);
+        assert_eq!(
+            decompress_if_needed(frames.clone()).unwrap(),
+            json.repeat(2)
+        );
+        frames.truncate(frame.len() + 6);
+        let err = decompress_if_needed(frames).unwrap_err();
+        assert!(err.to_string().starts_with("zstd: frame 1:"), "{err}");
+        let mut corrupt = frame.repeat(3);
+        corrupt[2 * frame.len()] ^= 0xff;
+        let err = decompress_if_needed(corrupt).unwrap_err();
+        assert!(err.to_string().starts_with("zstd: frame 2:"), "{err}");
+    }
+
+    #[test]
+    fn condenses_har_entries() {
+        let har = json!({"log": {"version": "1.2", "entries": [
+            {
+                "request": {"method": "GET", "url": "https://api.example.com/items"},
+                "response": {"status": 200, "content": {
+                    "mimeType": "application/json", "text": "{\"items\": [1, 2]}"
+                }}
+            },
+            {
+                "request": {"method": "GET", "url": "https://example.com/"},
+                "response": {"status": 304, "content": {
+                    "mimeType": "text/html", "encoding": "base64", "text": "PGh0bWw+"
+                }}
+            }
+        ]}});
+        let value = parse_har(&har.to_string()).unwrap();
+        assert_eq!(
+            value["0 GET https://api.example.com/items 200"]["response"]["content"]["text"],
+            json!({"items": [1, 2]})
+        );
+        assert_eq!(
+            value["1 GET https://example.com/ 304"]["response"]["content"]["text"],
+            json!("<text/html, 6 bytes>")
+        );
+        assert!(parse_har(r#"{"log": {}}"#).is_err());
+    }
+
+    fn read_unlimited(source: InputSource) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut limit = SizeLimit {
+            max_size: None,
+            allow_larger: &|_| true,
+        };
+        read_input(source, false, &mut limit, &|_| {})
+    }
+
+    #[test]
+    fn reads_inline_and_file_sources() {
+        let raw = read_unlimited(InputSource::Inline(r#"{"a": 1}"#.to_string())).unwrap();
+        assert_eq!(raw, br#"{"a": 1}"#);
+
+        let path = std::env::temp_dir().join(format!("json_viewer-{}.json", std::process::id()));
+        fs::write(&path, "[1, 2]").unwrap();
+        let raw = read_unlimited(InputSource::File(path.clone()));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(raw.unwrap(), b"[1, 2]");
+
+        assert!(read_unlimited(InputSource::File(path)).is_err());
+    }
+
+    #[test]
+    fn reports_malformed_ndjson_lines_by_strictness() {
+        let input = "{\"a\": 1}\n\n{\"a\": \n[2]\n";
+        let err = parse_ndjson(input, true).unwrap_err().to_string();
+        assert!(err.starts_with("line 3: "), "{err}");
+
+        let value = parse_ndjson(input, false).unwrap();
+        assert_eq!(value.as_array().unwrap().len(), 3);
+        assert_eq!(value[0], json!({"a": 1}));
+        assert!(value[1].as_str().unwrap().starts_with("<line 3: "));
+        assert_eq!(value[2], json!([2]));
+    }
+
+    #[test]
+    fn names_byte_offset_of_partial_trailing_document() {
+        assert_eq!(parse_json_stream(r#"{"a": 1}"#).unwrap(), json!({"a": 1}));
+        assert_eq!(
+            parse_json_stream("{\"a\": 1}\n{\"b\": 2}").unwrap(),
+            json!([{"a": 1}, {"b": 2}])
+        );
+        let err = parse_json_stream("{\"a\": 1}\n  {\"b\": ")
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("document 2 at byte 11: "), "{err}");
+    }
+
+    #[test]
+    fn tells_json_lines_from_other_text() {
+        let cases = [
+            // JSON
+            (r#"{"a": 1}"#, InputFormat::Json),
+            ("{\n  \"a\": 1,\n  \"b\": 2\n}", InputFormat::Json),
+            (r#"{"a": 1} {"b": 2}"#, InputFormat::Json),
+            // NDJSON, blank lines don't matter
+            ("{\"a\": 1}\n{\"a\": 2}\n", InputFormat::NdJson),
+            ("\n[1]\n\n\"x\"\n", InputFormat::NdJson),
+            ("{\"a\": 1}\n{\"a\": ", InputFormat::NdJson),
+            // other text formats are recognised by their extension or flag, not detected
+            ("a: 1\nb: [2, 3]\n", InputFormat::Json),
+            ("a = 1\n[table]\nb = 2\n", InputFormat::Json),
+            ("name,age\nann,3\n", InputFormat::Json),
+            // ambiguous: a one-column CSV of numbers is valid JSON Lines as well
+            ("1\n2\n3\n", InputFormat::NdJson),
+            ("", InputFormat::Json),
+        ];
+        for (input, format) in cases {
+            assert_eq!(detect_format(input), format, "{input:?}");
+        }
+    }
+}
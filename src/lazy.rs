@@ -0,0 +1,428 @@
+use serde_json::{Map, Value};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom},
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use crate::{get_value_at_path_mut, JsonPointer};
+
+/// JSON file read a level at a time: objects and arrays are left empty, with their byte
+/// spans in the file kept, until their children are loaded on expanding them
+pub struct LazyDocument {
+    path: PathBuf,
+    /// Spans of the objects and arrays not loaded yet, by the document path of the object
+    /// or array they are in
+    unloaded: HashMap<Vec<JsonPointer>, Level>,
+}
+
+impl LazyDocument {
+    /// Reads the top level of the file, `None` when it is not a single object or array
+    /// (e.g. JSON Lines) and has to be parsed as a whole
+    pub fn open(path: &Path) -> Result<Option<(Self, Value)>, Box<dyn Error>> {
+        let mut scanner = Scanner::new(BufReader::new(fs::File::open(path)?), 0);
+        scanner.skip_bom()?;
+        if !matches!(scanner.skip_space()?, Some(b'{' | b'[')) {
+            return Ok(None);
+        }
+        let (root, level) = scanner.read_level()?;
+        if scanner.skip_space()?.is_some() {
+            return Ok(None);
+        }
+        let mut document = Self {
+            path: path.to_path_buf(),
+            unloaded: HashMap::new(),
+        };
+        if !level.is_empty() {
+            document.unloaded.insert(Vec::new(), level);
+        }
+        Ok(Some((document, root)))
+    }
+
+    fn span(&self, path: &[JsonPointer]) -> Option<&Range<u64>> {
+        let (pointer, parent) = path.split_last()?;
+        self.unloaded.get(parent)?.get(pointer)
+    }
+
+    pub fn is_unloaded(&self, path: &[JsonPointer]) -> bool {
+        self.span(path).is_some()
+    }
+
+    /// Whether nothing under `path` is left to load
+    pub fn is_complete(&self, path: &[JsonPointer]) -> bool {
+        !self.is_unloaded(path) && !self.unloaded.keys().any(|parent| parent.starts_with(path))
+    }
+
+    /// Reads the children of the object or array at `path` into `root`, their own
+    /// objects and arrays are left to load
+    pub fn load(&mut self, root: &mut Value, path: &[JsonPointer]) -> Result<(), Box<dyn Error>> {
+        let Some(span) = self.span(path).cloned() else {
+            return Ok(());
+        };
+        let (value, level) = Scanner::new(self.reader(&span)?, span.start).read_level()?;
+        *get_value_at_path_mut(root, path).ok_or("no such node")? = value;
+        self.loaded(path);
+        if !level.is_empty() {
+            self.unloaded.insert(path.to_vec(), level);
+        }
+        Ok(())
+    }
+
+    /// Reads the objects and arrays on the way to `path`, and the one at it
+    pub fn load_path(
+        &mut self,
+        root: &mut Value,
+        path: &[JsonPointer],
+    ) -> Result<(), Box<dyn Error>> {
+        for len in 1..=path.len() {
+            self.load(root, &path[..len])?;
+        }
+        Ok(())
+    }
+
+    /// Reads everything under `path` into `root`, e.g. before copying the node
+    pub fn load_subtree(
+        &mut self,
+        root: &mut Value,
+        path: &[JsonPointer],
+    ) -> Result<(), Box<dyn Error>> {
+        let mut spans: Vec<_> = self
+            .span(path)
+            .map(|span| (path.to_vec(), span.clone()))
+            .into_iter()
+            .collect();
+        for (parent, level) in &self.unloaded {
+            if parent.starts_with(path) {
+                spans.extend(level.spans().map(|(pointer, span)| {
+                    let mut child = parent.clone();
+                    child.push(pointer);
+                    (child, span)
+                }));
+            }
+        }
+        for (child, span) in spans {
+            let value: Value = serde_json::from_reader(self.reader(&span)?)
+                .map_err(|err| format!("byte {}: {err}", span.start))?;
+            *get_value_at_path_mut(root, &child).ok_or("no such node")? = value;
+            self.loaded(&child);
+        }
+        Ok(())
+    }
+
+    /// Forgets the span of the object or array at `path`
+    fn loaded(&mut self, path: &[JsonPointer]) {
+        let Some((pointer, parent)) = path.split_last() else {
+            return;
+        };
+        if let Some(level) = self.unloaded.get_mut(parent) {
+            level.remove(pointer);
+            if level.is_empty() {
+                self.unloaded.remove(parent);
+            }
+        }
+    }
+
+    fn reader(&self, span: &Range<u64>) -> io::Result<BufReader<io::Take<fs::File>>> {
+        let mut file = fs::File::open(&self.path)?;
+        file.seek(SeekFrom::Start(span.start))?;
+        Ok(BufReader::new(file.take(span.end - span.start)))
+    }
+}
+
+/// Byte spans of the objects and arrays in an object (by key) or array (by index),
+/// kept apart so the elements of long arrays need no key of their own
+#[derive(Default)]
+struct Level {
+    keys: HashMap<String, Range<u64>>,
+    indices: HashMap<usize, Range<u64>>,
+}
+
+impl Level {
+    fn get(&self, pointer: &JsonPointer) -> Option<&Range<u64>> {
+        match pointer {
+            JsonPointer::ObjectKey(key) => self.keys.get(key),
+            JsonPointer::ArrayIdx(index) => self.indices.get(index),
+            _ => None,
+        }
+    }
+
+    fn insert(&mut self, pointer: JsonPointer, span: Range<u64>) {
+        match pointer {
+            JsonPointer::ObjectKey(key) => {
+                self.keys.insert(key, span);
+            }
+            JsonPointer::ArrayIdx(index) => {
+                self.indices.insert(index, span);
+            }
+            _ => {}
+        }
+    }
+
+    fn remove(&mut self, pointer: &JsonPointer) {
+        match pointer {
+            JsonPointer::ObjectKey(key) => {
+                self.keys.remove(key);
+            }
+            JsonPointer::ArrayIdx(index) => {
+                self.indices.remove(index);
+            }
+            _ => {}
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.indices.is_empty()
+    }
+
+    fn spans(&self) -> impl Iterator<Item = (JsonPointer, Range<u64>)> + '_ {
+        let keys = self
+            .keys
+            .iter()
+            .map(|(key, span)| (JsonPointer::ObjectKey(key.clone()), span.clone()));
+        let indices = self
+            .indices
+            .iter()
+            .map(|(index, span)| (JsonPointer::ArrayIdx(*index), span.clone()));
+        keys.chain(indices)
+    }
+}
+
+/// Reads a level of JSON from a stream, keeping track of the byte offset
+struct Scanner<R> {
+    reader: R,
+    offset: u64,
+}
+
+impl<R: BufRead> Scanner<R> {
+    fn new(reader: R, offset: u64) -> Self {
+        Self { reader, offset }
+    }
+
+    fn peek(&mut self) -> io::Result<Option<u8>> {
+        Ok(self.reader.fill_buf()?.first().copied())
+    }
+
+    fn advance(&mut self, count: usize) {
+        self.reader.consume(count);
+        self.offset += count as u64;
+    }
+
+    fn skip_bom(&mut self) -> io::Result<()> {
+        if self.reader.fill_buf()?.starts_with(&[0xef, 0xbb, 0xbf]) {
+            self.advance(3);
+        }
+        Ok(())
+    }
+
+    /// Next byte that is not whitespace, left unread
+    fn skip_space(&mut self) -> io::Result<Option<u8>> {
+        while let Some(byte) = self.peek()? {
+            if !byte.is_ascii_whitespace() {
+                return Ok(Some(byte));
+            }
+            self.advance(1);
+        }
+        Ok(None)
+    }
+
+    fn error(&self, found: Option<u8>, expected: &str) -> Box<dyn Error> {
+        match found {
+            Some(byte) => format!(
+                "byte {}: expected {expected}, found {:?}",
+                self.offset,
+                char::from(byte)
+            ),
+            None => format!(
+                "byte {}: expected {expected}, found end of input",
+                self.offset
+            ),
+        }
+        .into()
+    }
+
+    /// The object or array up next, with the spans of the objects and arrays in it,
+    /// which are left empty
+    fn read_level(&mut self) -> Result<(Value, Level), Box<dyn Error>> {
+        let mut level = Level::default();
+        let found = self.skip_space()?;
+        let close = match found {
+            Some(b'{') => b'}',
+            Some(b'[') => b']',
+            _ => return Err(self.error(found, "'{' or '['")),
+        };
+        self.advance(1);
+        let mut object = Map::new();
+        let mut array = Vec::new();
+        if self.skip_space()? == Some(close) {
+            self.advance(1);
+        } else {
+            loop {
+                let pointer = if close == b'}' {
+                    let key: String = self.read_scalar()?;
+                    match self.skip_space()? {
+                        Some(b':') => self.advance(1),
+                        found => return Err(self.error(found, "':'")),
+                    }
+                    JsonPointer::ObjectKey(key)
+                } else {
+                    JsonPointer::ArrayIdx(array.len())
+                };
+                let value = match self.skip_space()? {
+                    Some(open @ (b'{' | b'[')) => {
+                        let start = self.offset;
+                        self.skip_container()?;
+                        level.insert(pointer.clone(), start..self.offset);
+                        match open {
+                            b'{' => Value::Object(Map::new()),
+                            _ => Value::Array(Vec::new()),
+                        }
+                    }
+                    _ => self.read_scalar()?,
+                };
+                match pointer {
+                    JsonPointer::ObjectKey(key) => {
+                        object.insert(key, value);
+                    }
+                    _ => array.push(value),
+                }
+                match self.skip_space()? {
+                    Some(b',') => self.advance(1),
+                    Some(byte) if byte == close => {
+                        self.advance(1);
+                        break;
+                    }
+                    found => return Err(self.error(found, &format!("',' or '{}'", close as char))),
+                }
+            }
+        }
+        let value = match close {
+            b'}' => Value::Object(object),
+            _ => Value::Array(array),
+        };
+        Ok((value, level))
+    }
+
+    /// String, number, `true`, `false` or `null` up next
+    fn read_scalar<T: serde::de::DeserializeOwned>(&mut self) -> Result<T, Box<dyn Error>> {
+        let start = self.offset;
+        let mut text = Vec::new();
+        if self.skip_space()? == Some(b'"') {
+            let mut escaped = false;
+            loop {
+                let Some(byte) = self.peek()? else {
+                    return Err(self.error(None, "'\"'"));
+                };
+                text.push(byte);
+                self.advance(1);
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' if text.len() > 1 => break,
+                    _ => {}
+                }
+            }
+        } else {
+            while let Some(byte) = self.peek()? {
+                if byte.is_ascii_whitespace() || matches!(byte, b',' | b':' | b']' | b'}') {
+                    break;
+                }
+                text.push(byte);
+                self.advance(1);
+            }
+        }
+        serde_json::from_slice(&text).map_err(|err| format!("byte {start}: {err}").into())
+    }
+
+    /// Moves past the object or array up next without parsing it, only strings are
+    /// followed to find where it ends
+    fn skip_container(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut depth = 0usize;
+        let mut in_string = false;
+        let mut escaped = false;
+        loop {
+            let buffer = self.reader.fill_buf()?;
+            if buffer.is_empty() {
+                return Err(self.error(None, "end of object or array"));
+            }
+            let mut used = 0;
+            let mut done = false;
+            for &byte in buffer {
+                used += 1;
+                if in_string {
+                    match byte {
+                        _ if escaped => escaped = false,
+                        b'\\' => escaped = true,
+                        b'"' => in_string = false,
+                        _ => {}
+                    }
+                    continue;
+                }
+                match byte {
+                    b'"' => in_string = true,
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            done = true;
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            self.advance(used);
+            if done {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn loads_levels_as_they_are_expanded() {
+        let text =
+            "\u{feff}{\"a\": {\"b\": [1, {\"c\": \"}]\\\"\"}], \"d\": {}}, \"e\": [], \"f\": 2.5}";
+        let path =
+            std::env::temp_dir().join(format!("json_viewer-lazy-{}.json", std::process::id()));
+        fs::write(&path, text).unwrap();
+        let (mut document, mut root) = LazyDocument::open(&path).unwrap().unwrap();
+        let key = |key: &str| JsonPointer::ObjectKey(key.to_string());
+        assert_eq!(root, json!({"a": {}, "e": [], "f": 2.5}));
+        assert!(document.is_unloaded(&[key("a")]));
+        assert!(!document.is_complete(&[]));
+
+        document.load(&mut root, &[key("a")]).unwrap();
+        assert_eq!(root["a"], json!({"b": [], "d": {}}));
+        let b = [key("a"), key("b")];
+        document.load(&mut root, &b).unwrap();
+        assert_eq!(root["a"]["b"], json!([1, {}]));
+        assert!(document.is_unloaded(&[&b[..], &[JsonPointer::ArrayIdx(1)]].concat()));
+
+        document.load_subtree(&mut root, &[]).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(document.is_complete(&[]));
+        assert_eq!(root, serde_json::from_str::<Value>(&text[3..]).unwrap());
+    }
+
+    #[test]
+    fn leaves_other_input_to_the_parser() {
+        let path =
+            std::env::temp_dir().join(format!("json_viewer-lines-{}.json", std::process::id()));
+        for text in ["{\"a\": 1}\n{\"a\": 2}\n", "\"text\"", "  "] {
+            fs::write(&path, text).unwrap();
+            assert!(LazyDocument::open(&path).unwrap().is_none(), "{text:?}");
+        }
+        fs::write(&path, "{\"a\": 1 \"b\": 2}").unwrap();
+        let err = LazyDocument::open(&path).err().unwrap().to_string();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(err, "byte 8: expected ',' or '}', found '\"'");
+    }
+}
@@ -0,0 +1,258 @@
+use crate::JsonPointer;
+
+/// Source text kept for locating values at most, larger documents show no line and column
+pub const MAX_LOCATED_SIZE: usize = 64 * 1024 * 1024;
+
+/// Byte offset where the value at `path` starts in the JSON source it was parsed from.
+/// Comments and trailing commas are skipped, concatenated documents (NDJSON) are the
+/// elements of the root array. `None` when the path isn't in the text, e.g. after a jq filter.
+pub fn locate(text: &str, path: &[JsonPointer]) -> Option<usize> {
+    let mut scanner = Scanner {
+        text,
+        bytes: text.as_bytes(),
+        pos: 0,
+    };
+    scanner.skip_space();
+    let first = scanner.pos;
+    let single = scanner.skip_value().is_some() && {
+        scanner.skip_space();
+        scanner.pos == text.len()
+    };
+    scanner.pos = first;
+    if single {
+        return scanner.find(path);
+    }
+    let Some(document) = path.iter().position(|pointer| !is_transparent(pointer)) else {
+        return Some(first);
+    };
+    let JsonPointer::ArrayIdx(index) = &path[document] else {
+        return None;
+    };
+    for _ in 0..*index {
+        scanner.skip_value()?;
+        scanner.skip_space();
+        if scanner.bytes.get(scanner.pos) == Some(&b',') {
+            scanner.pos += 1;
+            scanner.skip_space();
+        }
+    }
+    scanner.find(&path[document + 1..])
+}
+
+/// 1-based line and column, in characters
+pub type Position = (usize, usize);
+
+/// Line and column of a byte offset
+pub fn line_col(text: &str, offset: usize) -> Position {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// Segments that only group the tree, not steps into the document
+fn is_transparent(pointer: &JsonPointer) -> bool {
+    matches!(pointer, JsonPointer::None | JsonPointer::ArrayPage(..))
+}
+
+struct Scanner<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Scanner<'_> {
+    /// Walks down from the value at the current position
+    fn find(&mut self, path: &[JsonPointer]) -> Option<usize> {
+        for pointer in path {
+            self.skip_space();
+            match pointer {
+                JsonPointer::None | JsonPointer::ArrayPage(..) => {}
+                JsonPointer::ObjectKey(key) => self.enter_member(key)?,
+                JsonPointer::ArrayIdx(index) => self.enter_element(*index)?,
+            }
+        }
+        self.skip_space();
+        (self.pos < self.bytes.len()).then_some(self.pos)
+    }
+
+    /// Moves to the value of `key` in the object at the current position. The last of
+    /// duplicate keys wins, like in the parsed document.
+    fn enter_member(&mut self, key: &str) -> Option<()> {
+        self.expect(b'{')?;
+        let mut found = None;
+        loop {
+            self.skip_space();
+            match self.bytes.get(self.pos)? {
+                b'}' => break,
+                b'"' => {}
+                _ => return None,
+            }
+            let start = self.pos;
+            self.skip_string()?;
+            let raw = &self.text[start + 1..self.pos - 1];
+            let matches = if raw.contains('\\') {
+                serde_json::from_str::<String>(&self.text[start..self.pos]).ok()? == key
+            } else {
+                raw == key
+            };
+            self.skip_space();
+            self.expect(b':')?;
+            self.skip_space();
+            if matches {
+                found = Some(self.pos);
+            }
+            self.skip_value()?;
+            self.skip_space();
+            match self.bytes.get(self.pos)? {
+                b',' => self.pos += 1,
+                b'}' => break,
+                _ => return None,
+            }
+        }
+        self.pos = found?;
+        Some(())
+    }
+
+    /// Moves to element `index` of the array at the current position
+    fn enter_element(&mut self, index: usize) -> Option<()> {
+        self.expect(b'[')?;
+        for _ in 0..index {
+            self.skip_space();
+            self.skip_value()?;
+            self.skip_space();
+            self.expect(b',')?;
+        }
+        self.skip_space();
+        match self.bytes.get(self.pos)? {
+            b']' => None,
+            _ => Some(()),
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        (self.bytes.get(self.pos) == Some(&byte)).then(|| self.pos += 1)
+    }
+
+    /// Whitespace, `//` and `/* */` comments
+    fn skip_space(&mut self) {
+        while let Some(&byte) = self.bytes.get(self.pos) {
+            match byte {
+                b' ' | b'\t' | b'\n' | b'\r' => self.pos += 1,
+                b'/' if self.bytes.get(self.pos + 1) == Some(&b'/') => {
+                    self.pos = match self.text[self.pos..].find('\n') {
+                        Some(end) => self.pos + end,
+                        None => self.bytes.len(),
+                    };
+                }
+                b'/' if self.bytes.get(self.pos + 1) == Some(&b'*') => {
+                    self.pos = match self.text[self.pos + 2..].find("*/") {
+                        Some(end) => self.pos + 2 + end + 2,
+                        None => self.bytes.len(),
+                    };
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn skip_string(&mut self) -> Option<()> {
+        self.pos += 1;
+        loop {
+            match self.bytes.get(self.pos)? {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    return Some(());
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    /// Skips the value at the current position, nested ones included
+    fn skip_value(&mut self) -> Option<()> {
+        match self.bytes.get(self.pos)? {
+            b'"' => self.skip_string(),
+            b'{' | b'[' => {
+                let mut depth = 0usize;
+                loop {
+                    match self.bytes.get(self.pos)? {
+                        b'"' => {
+                            self.skip_string()?;
+                            continue;
+                        }
+                        b'/' => {
+                            let start = self.pos;
+                            self.skip_space();
+                            if self.pos != start {
+                                continue;
+                            }
+                        }
+                        b'{' | b'[' => depth += 1,
+                        b'}' | b']' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                self.pos += 1;
+                                return Some(());
+                            }
+                        }
+                        _ => {}
+                    }
+                    self.pos += 1;
+                }
+            }
+            _ => {
+                let start = self.pos;
+                while let Some(byte) = self.bytes.get(self.pos) {
+                    if matches!(
+                        byte,
+                        b',' | b']' | b'}' | b' ' | b'\t' | b'\n' | b'\r' | b'/'
+                    ) {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                (self.pos > start).then_some(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(text: &str, path: &[JsonPointer]) -> Option<Position> {
+        locate(text, path).map(|offset| line_col(text, offset))
+    }
+
+    #[test]
+    fn locates_values_in_source() {
+        let key = |key: &str| JsonPointer::ObjectKey(key.to_string());
+        let text =
+            "{\n  // note\n  \"a\": [1, {\"b\\u0063\": \"x,]\"}],\n  \"é\": null,\n  \"a\": 2,\n}";
+        assert_eq!(position(text, &[]), Some((1, 1)));
+        assert_eq!(position(text, &[JsonPointer::None, key("a")]), Some((5, 8)));
+        assert_eq!(position(text, &[key("é")]), Some((4, 8)));
+        let first = "{\"a\": [1, {\"bc\": 3}], \"n\": 1}";
+        assert_eq!(
+            position(first, &[key("a"), JsonPointer::ArrayIdx(1), key("bc")]),
+            Some((1, 18))
+        );
+        assert_eq!(position(first, &[key("a"), JsonPointer::ArrayIdx(2)]), None);
+        assert_eq!(position(first, &[key("zz")]), None);
+
+        let escaped = "{\"b\\u0063\": true}";
+        assert_eq!(position(escaped, &[key("bc")]), Some((1, 13)));
+
+        let stream = "{\"id\": 1}\n{\"id\": 2}\n";
+        assert_eq!(
+            position(stream, &[JsonPointer::ArrayIdx(1), key("id")]),
+            Some((2, 8))
+        );
+        assert_eq!(position(stream, &[]), Some((1, 1)));
+    }
+}
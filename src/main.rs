@@ -1,5 +1,6 @@
+use chrono::{DateTime, Utc};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -11,119 +12,3396 @@ use ratatui::{
     Terminal,
 };
 use serde_json::Value;
-use std::{env, error::Error, fmt, hash::Hash, io, io::Read, process::exit};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    error::Error,
+    fmt, fs,
+    hash::Hash,
+    io::{self, BufRead, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::exit,
+    rc::Rc,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+use tui_input::{backend::crossterm::EventHandler, Input};
 use tui_tree_widget::{Tree, TreeItem, TreeState};
 
-#[derive(Default, Clone, PartialEq, Eq, Hash, Debug)]
-pub enum JsonPointer {
-    ObjectKey(String),
-    ArrayIdx(usize),
-    #[default]
-    None,
-}
+mod bookmarks;
+mod config;
+mod diff;
+mod filter;
+mod finder;
+mod history;
+mod input;
+mod lazy;
+mod location;
+mod nested;
+mod path;
+mod query;
+mod schema;
+mod search;
+mod session;
+mod theme;
+mod tree;
+mod undo;
+mod watcher;
+
+use bookmarks::{Bookmark, BookmarkStore};
+use config::{load_config, Config, KeyMap};
+use diff::{json_diff, DiffMark};
+use filter::{pipe_to_command, run_jq};
+use finder::{PathIndex, MAX_INDEXED_PATHS};
+use history::SearchHistory;
+use input::{
+    format_size, load_input, Document, InputFormat, InputOptions, InputSource, Progress,
+    DEFAULT_MAX_SIZE,
+};
+use lazy::LazyDocument;
+use location::{line_col, locate, Position};
+use nested::{
+    collapse_at, expand_at, expand_nested, parse_embedded, replace_embedded, try_decode_base64,
+    url_decode, DecodedValue, Embedding, RawStrings,
+};
+use path::{
+    format_path, parse_path, resolve, resolve_path, selected_json_pointer, PathFormat, Unresolved,
+};
+use query::{run_jsonpath, QueryResult};
+use schema::Schema;
+use search::{find_key, scalar_text, SearchScope, SearchState};
+use session::{load_session, save_session, SessionState};
+use theme::{load_theme, Colors};
+use tree::{
+    compact_preview, compute_node_info, count_nodes, document_path, opened_to_depth,
+    render_tree_to_text, timestamp_date, tree_path, ItemsBuilder, JsonType, NumberFormat, SortMode,
+    DEFAULT_PAGE_SIZE, DEFAULT_PREVIEW_CHARS,
+};
+use undo::{EditOp, UndoStack};
+use watcher::FileWatcher;
+
+#[derive(Default, Clone, PartialEq, Eq, Hash, Debug, serde::Serialize, serde::Deserialize)]
+pub enum JsonPointer {
+    ObjectKey(String),
+    ArrayIdx(usize),
+    /// Page of a long array, first and last index of its elements
+    ArrayPage(usize, usize),
+    #[default]
+    None,
+}
+
+impl fmt::Display for JsonPointer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ObjectKey(key) => write!(f, "{key}"),
+            Self::ArrayIdx(index) => write!(f, "{index}"),
+            Self::ArrayPage(first, last) => write!(f, "[{first}..{last}]"),
+            Self::None => Ok(()),
+        }
+    }
+}
+
+/// System clipboard, connected on first use
+#[derive(Default)]
+struct Clipboard {
+    inner: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    fn set_text(&mut self, text: String) -> Result<(), String> {
+        let clipboard = match &mut self.inner {
+            Some(clipboard) => clipboard,
+            None => self.inner.insert(
+                arboard::Clipboard::new()
+                    .map_err(|err| format!("clipboard is not available ({err})"))?,
+            ),
+        };
+        clipboard.set_text(text).map_err(|err| err.to_string())
+    }
+}
+
+struct CommandEntry {
+    key: &'static str,
+    description: &'static str,
+    action: fn(&mut App),
+}
+
+const COMMANDS: &[CommandEntry] = &[
+    CommandEntry {
+        key: "q",
+        description: "Quit",
+        action: |app| app.quit = true,
+    },
+    CommandEntry {
+        key: "c",
+        description: "Command palette",
+        action: |app| app.show_cmd_popup = false,
+    },
+    CommandEntry {
+        key: "Enter/Space",
+        description: "Expand/collapse node",
+        action: |app| {
+            app.state.toggle_selected();
+        },
+    },
+    CommandEntry {
+        key: "Home",
+        description: "First node",
+        action: |app| {
+            app.state.select_first(&app.items);
+        },
+    },
+    CommandEntry {
+        key: "End",
+        description: "Last node",
+        action: |app| {
+            app.state.select_last(&app.items);
+        },
+    },
+    CommandEntry {
+        key: ">",
+        description: "Scroll tree right (< left, also Shift+Left/Right)",
+        action: |app| {
+            app.scroll_tree(true);
+        },
+    },
+    CommandEntry {
+        key: "e",
+        description: "Expand all",
+        action: |app| {
+            app.expand_all();
+        },
+    },
+    CommandEntry {
+        key: "E",
+        description: "Collapse all",
+        action: |app| {
+            app.collapse_all();
+        },
+    },
+    CommandEntry {
+        key: "o",
+        description: "Sort keys ascending/descending/off",
+        action: |app| {
+            app.toggle_sort(false);
+        },
+    },
+    CommandEntry {
+        key: "O",
+        description: "Sort by value type on/off",
+        action: |app| {
+            app.toggle_sort(true);
+        },
+    },
+    CommandEntry {
+        key: "/",
+        description: "Search",
+        action: |app| {
+            app.start_search();
+        },
+    },
+    CommandEntry {
+        key: "?",
+        description: "Search below the selected node",
+        action: |app| {
+            app.start_subtree_search();
+        },
+    },
+    CommandEntry {
+        key: "f",
+        description: "Filter the tree as you type, Esc shows all again",
+        action: |app| {
+            app.start_narrow();
+        },
+    },
+    CommandEntry {
+        key: "n",
+        description: "Next match",
+        action: |app| {
+            app.next_match(true);
+        },
+    },
+    CommandEntry {
+        key: "N",
+        description: "Previous match",
+        action: |app| {
+            app.next_match(false);
+        },
+    },
+    CommandEntry {
+        key: "R",
+        description: "Pane listing all search matches",
+        action: |app| {
+            app.toggle_results();
+        },
+    },
+    CommandEntry {
+        key: "]",
+        description: "Next node with the same key",
+        action: |app| {
+            app.same_key(true);
+        },
+    },
+    CommandEntry {
+        key: "[",
+        description: "Previous node with the same key",
+        action: |app| {
+            app.same_key(false);
+        },
+    },
+    CommandEntry {
+        key: "Ctrl+F",
+        description: "Toggle fuzzy search",
+        action: |app| {
+            app.toggle_fuzzy();
+        },
+    },
+    CommandEntry {
+        key: "Ctrl+K",
+        description: "Search keys only, or keys and values",
+        action: |app| {
+            app.toggle_search_scope(SearchScope::Keys);
+        },
+    },
+    CommandEntry {
+        key: "Ctrl+V",
+        description: "Search values only, or keys and values",
+        action: |app| {
+            app.toggle_search_scope(SearchScope::Values);
+        },
+    },
+    CommandEntry {
+        key: "Ctrl+T",
+        description: "Toggle case-insensitive search",
+        action: |app| {
+            app.toggle_ignore_case();
+        },
+    },
+    CommandEntry {
+        key: "Ctrl+R",
+        description: "Toggle regex search",
+        action: |app| {
+            app.toggle_regex();
+        },
+    },
+    CommandEntry {
+        key: "Esc",
+        description: "Clear search",
+        action: |app| {
+            app.clear_search();
+        },
+    },
+    CommandEntry {
+        key: "g",
+        description: "Go to JSON Pointer or dotted path",
+        action: |app| app.goto_input = Some(String::new()),
+    },
+    CommandEntry {
+        key: "|",
+        description: "jq filter (empty for the original document)",
+        action: |app| app.filter_input = Some(String::new()),
+    },
+    CommandEntry {
+        key: "!",
+        description: "Pipe the selected value to a shell command",
+        action: |app| app.pipe_input = Some(String::new()),
+    },
+    CommandEntry {
+        key: "Ctrl+P",
+        description: "Find path (fuzzy)",
+        action: |app| {
+            app.open_finder();
+        },
+    },
+    CommandEntry {
+        key: ":",
+        description: "JSONPath query",
+        action: |app| app.query_input = Some(String::new()),
+    },
+    CommandEntry {
+        key: "b",
+        description: "Bookmark node",
+        action: |app| {
+            app.add_bookmark();
+        },
+    },
+    CommandEntry {
+        key: "B",
+        description: "Show bookmarks",
+        action: |app| {
+            app.show_bookmarks();
+        },
+    },
+    CommandEntry {
+        key: "m",
+        description: "Toggle bookmark",
+        action: |app| {
+            app.toggle_bookmark();
+        },
+    },
+    CommandEntry {
+        key: "'",
+        description: "Next bookmark",
+        action: |app| {
+            app.cycle_bookmark(true);
+        },
+    },
+    CommandEntry {
+        key: "\"",
+        description: "Previous bookmark",
+        action: |app| {
+            app.cycle_bookmark(false);
+        },
+    },
+    CommandEntry {
+        key: "y",
+        description: "Copy value",
+        action: |app| {
+            app.copy_selected();
+        },
+    },
+    CommandEntry {
+        key: "Y",
+        description: "Copy JSON Pointer of the node",
+        action: |app| {
+            app.copy_pointer();
+        },
+    },
+    CommandEntry {
+        key: "J",
+        description: "Expand JSON embedded in string, or restore the string",
+        action: |app| {
+            app.toggle_nested();
+        },
+    },
+    CommandEntry {
+        key: "u",
+        description: "Show URL-encoded string decoded, or raw again",
+        action: |app| {
+            app.toggle_url_decoded();
+        },
+    },
+    CommandEntry {
+        key: "d",
+        description: "Show base64 string decoded, or encoded again",
+        action: |app| {
+            app.toggle_base64();
+        },
+    },
+    CommandEntry {
+        key: "i",
+        description: "Edit value",
+        action: |app| {
+            app.start_edit();
+        },
+    },
+    CommandEntry {
+        key: "Ctrl+Z",
+        description: "Undo edit",
+        action: |app| {
+            app.undo_edit();
+        },
+    },
+    CommandEntry {
+        key: "Ctrl+Y",
+        description: "Redo edit",
+        action: |app| {
+            app.redo_edit();
+        },
+    },
+    CommandEntry {
+        key: "s",
+        description: "Export selected subtree to file",
+        action: |app| app.export_input = Some(String::new()),
+    },
+    CommandEntry {
+        key: "W",
+        description: "Write the tree as shown to a text file",
+        action: |app| {
+            app.write_tree_text();
+        },
+    },
+    CommandEntry {
+        key: "v",
+        description: "Show the selected value full-screen",
+        action: |app| {
+            app.show_fullscreen();
+        },
+    },
+    CommandEntry {
+        key: "t",
+        description: "Show Unix timestamps as dates or numbers",
+        action: |app| {
+            app.toggle_timestamps();
+        },
+    },
+    CommandEntry {
+        key: "#",
+        description: "Show numbers raw, thousands separated, scientific or human readable",
+        action: |app| {
+            app.cycle_number_format();
+        },
+    },
+    CommandEntry {
+        key: "p",
+        description: "Print document to stdout on exit",
+        action: |app| {
+            app.toggle_print();
+        },
+    },
+    CommandEntry {
+        key: "P",
+        description: "Open clipboard in place of an empty document",
+        action: |app| {
+            app.paste_clipboard();
+        },
+    },
+    CommandEntry {
+        key: "r",
+        description: "Reload input",
+        action: |app| {
+            app.reload_input();
+        },
+    },
+    CommandEntry {
+        key: "Tab",
+        description: "Switch between tree and value panes",
+        action: |app| {
+            app.toggle_focus();
+        },
+    },
+    CommandEntry {
+        key: "Ctrl+Right",
+        description: "Next document",
+        action: |app| app.tab_command = Some(TabCommand::Next),
+    },
+    CommandEntry {
+        key: "Ctrl+Left",
+        description: "Previous document",
+        action: |app| app.tab_command = Some(TabCommand::Prev),
+    },
+    CommandEntry {
+        key: "x",
+        description: "Close document",
+        action: |app| app.tab_command = Some(TabCommand::Close),
+    },
+];
+
+#[derive(Clone, Copy)]
+enum TabCommand {
+    Next,
+    Prev,
+    Close,
+    Select(usize),
+}
+
+#[allow(dead_code)]
+struct Content {
+    key: Vec<JsonPointer>,
+    value: String,
+}
+
+impl fmt::Debug for Content {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("")
+            .field(&self.key)
+            .field(&self.value)
+            .finish()
+    }
+}
+
+/// Leaf value being edited in place
+struct EditState {
+    path: Vec<JsonPointer>,
+    input: Input,
+    /// Why the typed text was rejected, shown until the next key
+    error: Option<String>,
+}
+
+/// What is printed to stdout once the viewer exits
+#[derive(Clone, Copy)]
+enum ExitPrint {
+    Selected,
+    All,
+    /// Path of the node picked with Enter
+    Path(PathFormat),
+}
+
+/// What `y` copies, pressing it again on the same node copies the next one
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CopyMode {
+    Value,
+    Pointer,
+    JqPath,
+}
+
+impl CopyMode {
+    fn next(self) -> Self {
+        match self {
+            Self::Value => Self::Pointer,
+            Self::Pointer => Self::JqPath,
+            Self::JqPath => Self::Value,
+        }
+    }
+}
+
+/// Printed to stdout once the viewer exits, JSON pretty-printed
+enum ExitOutput {
+    Json(Value),
+    Text(String),
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    #[default]
+    Tree,
+    Detail,
+    Results,
+}
+
+/// What fills the screen above the status line
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum ViewMode {
+    #[default]
+    Tree,
+    /// Value of the selected leaf alone, word-wrapped, scrolled by `scroll` lines
+    FullscreenValue { scroll: u16 },
+}
+
+struct App<'a> {
+    state: TreeState<JsonPointer>,
+    items: Vec<TreeItem<'a, JsonPointer>>,
+    root: Value,
+    status: String,
+    search: SearchState,
+    /// Committed queries, recalled with Up/Down in the search prompt
+    search_history: SearchHistory,
+    /// Opened nodes, selection and scroll offset before `/`, restored when it is cancelled
+    search_origin: Option<(Vec<Vec<JsonPointer>>, Vec<JsonPointer>, usize)>,
+    /// Live filter, only matching nodes with their ancestors and descendants are shown
+    narrow: SearchState,
+    narrow_input: Option<String>,
+    /// Typed into the live filter prompt but not applied yet, large documents wait for a pause
+    narrow_pending: Option<Instant>,
+    /// Opened nodes, selection and scroll offset before `f`, restored when the filter is cleared
+    narrow_origin: Option<(Vec<Vec<JsonPointer>>, Vec<JsonPointer>, usize)>,
+    goto_input: Option<String>,
+    query_input: Option<String>,
+    filter_input: Option<String>,
+    /// jq program the document is shown through, applied to `unfiltered`
+    filter: Option<String>,
+    unfiltered: Option<Value>,
+    pipe_input: Option<String>,
+    /// Shell command the selected value was piped to and what it printed, shown in a popup
+    pipe_output: Option<(String, String)>,
+    pipe_scroll: u16,
+    /// JSON text the document was parsed from, for the line and column of the selection
+    source_text: Option<String>,
+    /// Line and column of the selected node in `source_text`, and for which node
+    location: Option<(Vec<JsonPointer>, Option<Position>)>,
+    /// Why the JSONPath expression failed, shown after it until it is edited
+    query_error: Option<String>,
+    /// Results of the last JSONPath query, shown in a popup
+    query_results: Option<Vec<QueryResult>>,
+    query_state: ListState,
+    /// Pane listing all search matches below the tree
+    results_open: bool,
+    results_state: ListState,
+    /// Dotted paths of the document for the path finder, built on first use
+    path_index: Option<PathIndex>,
+    finder_input: Option<String>,
+    /// Entries of `path_index` matching the finder input, best first
+    finder_results: Vec<usize>,
+    finder_state: ListState,
+    /// Bookmarks of the document, shown in a popup
+    bookmarks: Option<Vec<Bookmark>>,
+    bookmark_state: ListState,
+    /// Document paths of the bookmarks, marked in the tree
+    bookmarked: HashSet<Vec<JsonPointer>>,
+    export_input: Option<String>,
+    edit_state: Option<EditState>,
+    focus: Pane,
+    view: ViewMode,
+    detail_width: u16,
+    detail_scroll: u16,
+    detail_path: Vec<JsonPointer>,
+    clipboard: Clipboard,
+    show_cmd_popup: bool,
+    cmd_state: ListState,
+    tab_command: Option<TabCommand>,
+    quit: bool,
+    title: String,
+    source: InputSource,
+    options: InputOptions,
+    watcher: Option<FileWatcher>,
+    reload_requested: bool,
+    /// Title and message of the failure shown over the tree
+    error_popup: Option<(&'static str, String)>,
+    session: bool,
+    /// Edits of the document, cleared when it is replaced
+    undo: UndoStack,
+    status_expires: Option<Instant>,
+    /// Rows of the tree items, as last rendered
+    tree_area: Rect,
+    /// Screen rows of the tree items cut at the right edge, as last rendered
+    truncated_rows: HashSet<u16>,
+    /// Mouse position over the tree and since when it rests there
+    hover: Option<(u16, u16, Instant)>,
+    breadcrumb_area: Rect,
+    h_scroll: u16,
+    last_click: Option<(Instant, u16)>,
+    /// Last copy by `y`, of which node and when
+    last_copy: Option<(Instant, Vec<JsonPointer>, CopyMode)>,
+    lazy: bool,
+    /// Large file read a level at a time, objects and arrays are read once expanded
+    lazy_document: Option<LazyDocument>,
+    /// Arrays longer than this are split into pages, 0 for no pages
+    page_size: usize,
+    /// Some array is split into pages, opened items are rebuilt like in lazy mode
+    paged: bool,
+    built_opened: HashSet<Vec<JsonPointer>>,
+    sort: SortMode,
+    /// Changed nodes in diff mode
+    diff: HashMap<Vec<JsonPointer>, DiffMark>,
+    /// Strings expanded into their embedded JSON
+    nested: RawStrings,
+    print_on_exit: Option<ExitPrint>,
+    /// Enter picks the selected node, printing its path on exit
+    select: Option<PathFormat>,
+    show_timestamps_as_dates: bool,
+    number_format: NumberFormat,
+    /// Characters of the preview after collapsed objects and arrays, 0 for none
+    preview_chars: usize,
+    /// Base64 strings shown decoded, by path
+    decoded: HashMap<Vec<JsonPointer>, DecodedValue>,
+    /// URL-encoded strings decoded so far, by path
+    url_decoded: HashMap<Vec<JsonPointer>, String>,
+    /// Paths of `url_decoded` shown decoded in the tree
+    url_shown: HashSet<Vec<JsonPointer>>,
+    /// Schema given with `--schema`, checked on every change of the document
+    schema: Option<Rc<Schema>>,
+    /// Messages of schema violations, by path
+    violations: HashMap<Vec<JsonPointer>, Vec<String>>,
+    colors: Colors,
+}
+
+impl<'a> App<'a> {
+    fn new(root: Value, source: InputSource, options: InputOptions) -> Self {
+        let mut app = Self {
+            state: TreeState::default(),
+            items: Vec::new(),
+            lazy: count_nodes(&root) > LAZY_NODES,
+            lazy_document: None,
+            page_size: DEFAULT_PAGE_SIZE,
+            paged: false,
+            built_opened: HashSet::new(),
+            sort: SortMode::default(),
+            diff: HashMap::new(),
+            nested: RawStrings::new(),
+            print_on_exit: None,
+            select: None,
+            show_timestamps_as_dates: false,
+            number_format: NumberFormat::default(),
+            preview_chars: 0,
+            decoded: HashMap::new(),
+            url_decoded: HashMap::new(),
+            url_shown: HashSet::new(),
+            schema: None,
+            violations: HashMap::new(),
+            colors: Colors::default(),
+            root,
+            status: String::new(),
+            search: SearchState::default(),
+            search_history: SearchHistory::load(),
+            search_origin: None,
+            narrow: SearchState::default(),
+            narrow_input: None,
+            narrow_pending: None,
+            narrow_origin: None,
+            goto_input: None,
+            query_input: None,
+            filter_input: None,
+            filter: None,
+            unfiltered: None,
+            pipe_input: None,
+            pipe_output: None,
+            pipe_scroll: 0,
+            source_text: None,
+            location: None,
+            query_error: None,
+            query_results: None,
+            query_state: ListState::default(),
+            results_open: false,
+            results_state: ListState::default(),
+            path_index: None,
+            finder_input: None,
+            finder_results: Vec::new(),
+            finder_state: ListState::default(),
+            bookmarks: None,
+            bookmark_state: ListState::default(),
+            bookmarked: HashSet::new(),
+            export_input: None,
+            edit_state: None,
+            focus: Pane::default(),
+            view: ViewMode::default(),
+            detail_width: DEFAULT_DETAIL_WIDTH,
+            detail_scroll: 0,
+            detail_path: Vec::new(),
+            clipboard: Clipboard::default(),
+            show_cmd_popup: false,
+            cmd_state: ListState::default().with_selected(Some(0)),
+            tab_command: None,
+            quit: false,
+            title: source.to_string(),
+            source,
+            options,
+            watcher: None,
+            reload_requested: false,
+            error_popup: None,
+            session: false,
+            undo: UndoStack::default(),
+            status_expires: None,
+            tree_area: Rect::default(),
+            truncated_rows: HashSet::new(),
+            hover: None,
+            breadcrumb_area: Rect::default(),
+            h_scroll: 0,
+            last_click: None,
+            last_copy: None,
+        };
+        if app.options.parse_nested {
+            expand_nested(&mut app.root, &mut Vec::new(), &mut app.nested);
+        }
+        if let InputSource::File(document) = &app.source {
+            app.bookmarked = BookmarkStore::load().paths(document);
+        }
+        app.rebuild_items();
+        app
+    }
+
+    /// Status message cleared after a while
+    fn flash(&mut self, message: &str) {
+        self.status = message.to_string();
+        self.status_expires = Some(Instant::now() + FLASH_DURATION);
+    }
+
+    fn expire_status(&mut self) {
+        if self
+            .status_expires
+            .is_some_and(|expires| expires <= Instant::now())
+        {
+            self.status.clear();
+            self.status_expires = None;
+        }
+    }
+
+    fn watch(&mut self) {
+        if let InputSource::File(path) = &self.source {
+            match FileWatcher::new(path) {
+                Ok(watcher) => self.watcher = Some(watcher),
+                Err(err) => self.status = format!("Watch failed: {err}"),
+            }
+        }
+    }
+
+    /// Reloads the document once the watcher reported a change, the old tree stays on parse errors
+    fn check_watched(&mut self) {
+        if self.watcher.as_ref().is_some_and(FileWatcher::changed) {
+            self.reload_requested = true;
+        }
+        if !self.reload_requested {
+            return;
+        }
+        self.reload_requested = false;
+        match self.reload() {
+            Ok(()) => {}
+            Err(err) => self.status = format!("Reload failed: {err}"),
+        }
+    }
+
+    /// Re-reads the input on request, a failure is shown in a popup over the old tree
+    fn reload_input(&mut self) -> bool {
+        if let InputSource::Stdin = self.source {
+            self.flash("stdin can't be reloaded");
+            return true;
+        }
+        match self.reload() {
+            Ok(()) => {}
+            Err(err) => {
+                self.error_popup = Some(("Reload failed", format!("{}: {err}", self.source)))
+            }
+        }
+        true
+    }
+
+    /// Validates the document against the schema, violations are annotated on the nodes
+    /// (on the nearest existing one for missing properties)
+    fn check_schema(&mut self) {
+        let Some(schema) = &self.schema else {
+            return;
+        };
+        self.violations.clear();
+        for violation in schema.validate(&self.root) {
+            let path = resolve_path(&self.root, &violation.pointer).unwrap_or_default();
+            self.violations
+                .entry(path)
+                .or_default()
+                .push(violation.message);
+        }
+    }
+
+    /// Replaces an empty document (`null`, `{}`, `[]` or `""`) by the one in the clipboard,
+    /// which `r` then reloads
+    fn paste_clipboard(&mut self) -> bool {
+        let empty = match &self.root {
+            Value::Null => true,
+            Value::Object(object) => object.is_empty(),
+            Value::Array(array) => array.is_empty(),
+            Value::String(text) => text.is_empty(),
+            _ => false,
+        };
+        if !empty {
+            self.flash("Only an empty document can be replaced, use --clipboard");
+            return true;
+        }
+        match load_input(&InputSource::Clipboard, &self.options, &|_| {}, &|_| false) {
+            Ok(document) => {
+                self.source = InputSource::Clipboard;
+                self.lazy_document = None;
+                self.title = self.source.to_string();
+                self.watcher = None;
+                self.filter = None;
+                self.unfiltered = None;
+                self.source_text = document.source;
+                self.replace_root(document.value);
+                match document.notice {
+                    Some(notice) => self.status = format!("Pasted, {notice}"),
+                    None => self.flash("Pasted from clipboard"),
+                }
+            }
+            Err(err) => self.status = format!("Paste failed: {err}"),
+        }
+        true
+    }
+
+    fn reload(&mut self) -> Result<(), Box<dyn Error>> {
+        if let (Some(_), InputSource::File(path)) = (&self.lazy_document, &self.source) {
+            if let Some((document, root)) = LazyDocument::open(path)? {
+                self.lazy_document = Some(document);
+                self.replace_root(root);
+                self.flash("Reloaded");
+                return Ok(());
+            }
+            self.lazy_document = None;
+        }
+        let document = load_input(&self.source, &self.options, &|_| {}, &|_| false)?;
+        let mut value = document.value;
+        if let Some(program) = &self.filter {
+            let filtered = run_jq(&value, program)?;
+            self.unfiltered = Some(std::mem::replace(&mut value, filtered));
+        }
+        self.source_text = document.source;
+        self.replace_root(value);
+        match document.notice {
+            Some(notice) => self.status = format!("Reloaded, {notice}"),
+            None => self.flash("Reloaded"),
+        }
+        Ok(())
+    }
+
+    /// Swaps the document keeping opened nodes and selection where their paths still exist
+    fn replace_root(&mut self, root: Value) {
+        let opened = self.state.get_all_opened();
+        let selected = self.state.selected();
+        let offset = self.state.get_offset();
+        self.root = root;
+        self.nested.clear();
+        self.decoded.clear();
+        self.url_decoded.clear();
+        self.url_shown.clear();
+        self.undo.clear();
+        self.path_index = None;
+        self.location = None;
+        if self.options.parse_nested {
+            expand_nested(&mut self.root, &mut Vec::new(), &mut self.nested);
+        }
+        if self.search.is_active() {
+            self.search.update(&self.root);
+        }
+        if self.narrow.is_active() {
+            self.narrow.update(&self.root);
+        }
+        self.check_schema();
+        self.rebuild_items();
+        self.state = TreeState::default();
+        self.restore(opened, selected, offset);
+    }
+
+    /// Opens and selects nodes whose paths exist, selection falls back to the nearest existing ancestor
+    fn restore(
+        &mut self,
+        opened: Vec<Vec<JsonPointer>>,
+        selected: Vec<JsonPointer>,
+        offset: usize,
+    ) {
+        let mut opened: Vec<_> = opened
+            .iter()
+            .map(|path| document_path(path).into_owned())
+            .collect();
+        opened.sort_by_key(Vec::len);
+        for path in &opened {
+            self.load_path(path);
+        }
+        // pages may differ, the array could have grown or shrunk
+        for path in opened {
+            if get_value_at_path(&self.root, &path).is_some() {
+                self.state
+                    .open(tree_path(&self.root, &path, self.page_size));
+            }
+        }
+        let mut selected = document_path(&selected).into_owned();
+        while get_value_at_path(&self.root, &selected).is_none() {
+            selected.pop();
+        }
+        self.state
+            .select(tree_path(&self.root, &selected, self.page_size));
+        self.state.scroll_down(offset);
+        self.sync_lazy_items();
+    }
+
+    /// Restores the saved session of the file and keeps saving it on exit. Edits left
+    /// unsaved by a crash are applied again while the file still has the values they replaced.
+    fn restore_session(&mut self) {
+        let InputSource::File(path) = &self.source else {
+            return;
+        };
+        if let Some(mut session) = load_session(path) {
+            let edited: Vec<_> = session.edits.paths().map(<[_]>::to_vec).collect();
+            for path in edited {
+                self.load_path(&path);
+            }
+            if session.edits.undoable() > 0 && session.edits.replay(&mut self.root) {
+                self.status = format!(
+                    "Restored {} unsaved edits, Ctrl+Z undoes them",
+                    session.edits.undoable()
+                );
+                self.undo = session.edits;
+                self.document_edited();
+            }
+            self.restore(session.open_nodes, session.selected, 0);
+        }
+        self.session = true;
+    }
+
+    /// Saves the session on close, edits made are not kept past it
+    fn save_session(&self) {
+        self.write_session(UndoStack::default());
+    }
+
+    fn write_session(&self, edits: UndoStack) {
+        let InputSource::File(path) = &self.source else {
+            return;
+        };
+        if self.session {
+            let session = SessionState {
+                open_nodes: self.state.get_all_opened(),
+                selected: self.state.selected(),
+                edits,
+            };
+            // nowhere to report it once the viewer is gone
+            let _ = save_session(path, &session);
+        }
+    }
+
+    /// `y` copies the value, pressed again soon on the same node its JSON Pointer, then jq path
+    fn copy_selected(&mut self) -> bool {
+        let selected = self.state.selected();
+        let now = Instant::now();
+        let mode = match &self.last_copy {
+            Some((time, path, mode))
+                if *path == selected && now.duration_since(*time) <= FLASH_DURATION =>
+            {
+                mode.next()
+            }
+            _ => CopyMode::Value,
+        };
+        self.last_copy = Some((now, selected, mode));
+        let next = match mode.next() {
+            CopyMode::Value => "value",
+            CopyMode::Pointer => "JSON Pointer",
+            CopyMode::JqPath => "jq path",
+        };
+        match self.copy_as(mode) {
+            Some(Ok(copied)) => self.status = format!("Copied {copied}, y again for {next}"),
+            Some(Err(err)) => self.status = format!("Copy failed: {err}"),
+            None => return false,
+        }
+        true
+    }
+
+    /// `Y` copies the JSON Pointer of the selected node
+    fn copy_pointer(&mut self) -> bool {
+        match self.copy_as(CopyMode::Pointer) {
+            Some(Ok(copied)) => self.status = format!("Copied {copied}"),
+            Some(Err(err)) => self.status = format!("Copy failed: {err}"),
+            None => return false,
+        }
+        true
+    }
+
+    /// Puts the selected node into the clipboard, telling what was copied.
+    /// `None` when nothing is selected.
+    fn copy_as(&mut self, mode: CopyMode) -> Option<Result<String, String>> {
+        let selected = document_path(&self.state.selected()).into_owned();
+        let (text, copied) = match mode {
+            CopyMode::Value => {
+                if let Err(err) = self.load_subtree(&selected) {
+                    return Some(Err(err));
+                }
+                let text = match get_value_at_path(&self.root, &selected)? {
+                    Value::String(text) => text.clone(),
+                    value @ (Value::Object(_) | Value::Array(_)) => {
+                        serde_json::to_string_pretty(value).unwrap_or_default()
+                    }
+                    value => value.to_string(),
+                };
+                (text, "value".to_string())
+            }
+            CopyMode::Pointer => {
+                let pointer = format_path(&selected, PathFormat::Pointer);
+                (pointer.clone(), format!("JSON Pointer {pointer}"))
+            }
+            CopyMode::JqPath => {
+                let path = format_path(&selected, PathFormat::Jq);
+                (path.clone(), format!("jq path {path}"))
+            }
+        };
+        Some(self.clipboard.set_text(text).map(|()| copied))
+    }
+
+    fn command_input(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Down => self.cmd_state.select(Some(
+                self.cmd_state
+                    .selected()
+                    .map_or(0, |index| (index + 1) % COMMANDS.len()),
+            )),
+            KeyCode::Up => self.cmd_state.select(Some(
+                self.cmd_state
+                    .selected()
+                    .map_or(0, |index| (index + COMMANDS.len() - 1) % COMMANDS.len()),
+            )),
+            KeyCode::Enter => {
+                self.show_cmd_popup = false;
+                if let Some(command) = self
+                    .cmd_state
+                    .selected()
+                    .and_then(|index| COMMANDS.get(index))
+                {
+                    (command.action)(self);
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('c') => self.show_cmd_popup = false,
+            KeyCode::Char('q') => self.quit = true,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Selects the clicked tree row, toggles it on double click
+    fn click(&mut self, column: u16, row: u16) -> bool {
+        let breadcrumb = self.breadcrumb_area;
+        if row == breadcrumb.y && (breadcrumb.left()..breadcrumb.right()).contains(&column) {
+            return self.click_breadcrumb(column - breadcrumb.x);
+        }
+        let inner = self.tree_area;
+        if !(inner.left()..inner.right()).contains(&column)
+            || !(inner.top()..inner.bottom()).contains(&row)
+        {
+            return false;
+        }
+        self.focus = Pane::Tree;
+        let Some(identifier) = self.item_at_row(row) else {
+            return false;
+        };
+        let now = Instant::now();
+        let double_click = self.last_click.is_some_and(|(time, last_row)| {
+            last_row == row && now.duration_since(time) <= DOUBLE_CLICK_INTERVAL
+        });
+        self.last_click = if double_click { None } else { Some((now, row)) };
+        self.state.select(identifier);
+        if double_click {
+            self.state.toggle_selected();
+        }
+        true
+    }
+
+    /// Tree path of the item shown on the screen row
+    fn item_at_row(&self, row: u16) -> Option<Vec<JsonPointer>> {
+        let index = self.state.get_offset() + usize::from(row.checked_sub(self.tree_area.y)?);
+        self.state
+            .flatten(&self.items)
+            .into_iter()
+            .nth(index)
+            .map(|flattened| flattened.identifier)
+    }
+
+    /// Notes where the mouse rests, the delay of the tooltip starts over when it moves
+    fn hover_at(&mut self, column: u16, row: u16) -> bool {
+        match self.hover {
+            Some((x, y, _)) if (x, y) == (column, row) => false,
+            _ => {
+                self.hover = Some((column, row, Instant::now()));
+                true
+            }
+        }
+    }
+
+    /// Full text of the truncated leaf the mouse has rested on for `HOVER_DELAY`
+    fn tooltip(&self) -> Option<(u16, u16, String)> {
+        let (column, row, since) = self.hover?;
+        let inner = self.tree_area;
+        if since.elapsed() < HOVER_DELAY
+            || !self.truncated_rows.contains(&row)
+            || !(inner.left()..inner.right()).contains(&column)
+        {
+            return None;
+        }
+        let path = self.item_at_row(row)?;
+        let text = match get_value_at_path(&self.root, &document_path(&path))? {
+            Value::Object(_) | Value::Array(_) => return None,
+            Value::String(text) => text.clone(),
+            value => value.to_string(),
+        };
+        Some((column, row, text))
+    }
+
+    /// Jumps to the ancestor whose breadcrumb segment is at the column
+    fn click_breadcrumb(&mut self, column: u16) -> bool {
+        let selected = self.state.selected();
+        let line = breadcrumb_line(&selected);
+        let mut x = usize::from(column + breadcrumb_scroll(&line, self.breadcrumb_area.width));
+        let Some(index) = line
+            .spans
+            .iter()
+            .position(|span| match x.checked_sub(span.width()) {
+                Some(rest) => {
+                    x = rest;
+                    false
+                }
+                None => true,
+            })
+        else {
+            return false;
+        };
+        if index % 2 == 1 {
+            // separator
+            return false;
+        }
+        self.focus = Pane::Tree;
+        match index / 2 {
+            0 => self.state.select_first(&self.items),
+            depth => self.state.select(selected[..depth].to_vec()),
+        }
+    }
+
+    fn scroll_tree(&mut self, right: bool) -> bool {
+        self.h_scroll = if right {
+            // leaves room for the off-screen rendering width
+            (self.h_scroll + H_SCROLL_STEP).min(u16::MAX / 2)
+        } else {
+            self.h_scroll.saturating_sub(H_SCROLL_STEP)
+        };
+        true
+    }
+
+    /// Moves focus on to the detail pane, the results pane and back to the tree, skipping
+    /// hidden ones
+    fn toggle_focus(&mut self) -> bool {
+        self.focus = match self.focus {
+            Pane::Tree if self.detail_width > 0 => Pane::Detail,
+            Pane::Tree | Pane::Detail if self.results_open => Pane::Results,
+            _ => Pane::Tree,
+        };
+        true
+    }
+
+    /// Opens the pane listing all search matches, focused, or closes it
+    fn toggle_results(&mut self) -> bool {
+        self.results_open = !self.results_open;
+        if self.results_open {
+            self.focus = Pane::Results;
+            self.results_state.select(self.search.current.or(Some(0)));
+        } else if self.focus == Pane::Results {
+            self.focus = Pane::Tree;
+        }
+        true
+    }
+
+    fn results_input(&mut self, code: KeyCode, keys: &KeyMap) -> bool {
+        let count = self.search.matches.len().min(MAX_LISTED_MATCHES);
+        let selected = self.results_state.selected().unwrap_or(0);
+        let last = count.saturating_sub(1);
+        match Some(code) {
+            code if code == keys.quit => self.quit = true,
+            code if code == keys.results => return self.toggle_results(),
+            code if code == keys.switch_pane => return self.toggle_focus(),
+            Some(KeyCode::Esc) => self.focus = Pane::Tree,
+            code if code == keys.down => self.results_state.select(Some((selected + 1).min(last))),
+            code if code == keys.up => self.results_state.select(Some(selected.saturating_sub(1))),
+            code if code == keys.page_down => {
+                self.results_state.select(Some((selected + 10).min(last)))
+            }
+            code if code == keys.page_up => {
+                self.results_state.select(Some(selected.saturating_sub(10)))
+            }
+            code if code == keys.first => self.results_state.select(Some(0)),
+            code if code == keys.last => self.results_state.select(Some(last)),
+            Some(KeyCode::Enter) => {
+                let Some(path) = self.search.matches.get(selected).cloned() else {
+                    return false;
+                };
+                self.search.current = Some(selected);
+                self.select_path(&path);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn scroll_detail(&mut self, lines: i16) -> bool {
+        self.detail_scroll = self.detail_scroll.saturating_add_signed(lines);
+        true
+    }
+
+    /// Full value of the selected node, pretty-printed if it is (or holds) JSON.
+    /// URL-encoded strings are followed by their decoded text, or the other way around.
+    fn selected_detail(&self) -> String {
+        let path = self.state.selected();
+        let path = document_path(&path);
+        if let Some(decoded) = self.decoded.get(path.as_ref()) {
+            return decoded.text().to_string();
+        }
+        if let (Some(decoded), Some(Value::String(raw))) = (
+            self.url_decoded.get(path.as_ref()),
+            get_value_at_path(&self.root, &path),
+        ) {
+            return if self.url_shown.contains(path.as_ref()) {
+                format!("{decoded}\n\nraw:\n{raw}")
+            } else {
+                format!("{raw}\n\nURL-decoded:\n{decoded}")
+            };
+        }
+        if let Some(lazy_document) = &self.lazy_document {
+            if !lazy_document.is_complete(&path) {
+                return "Not read from the file yet, expand it to read its children".to_string();
+            }
+        }
+        match get_value_at_path(&self.root, &path) {
+            Some(Value::String(text)) => match serde_json::from_str::<Value>(text) {
+                Ok(value @ (Value::Object(_) | Value::Array(_))) => {
+                    serde_json::to_string_pretty(&value).unwrap_or_default()
+                }
+                _ => text.clone(),
+            },
+            Some(value) => {
+                let text = serde_json::to_string_pretty(value).unwrap_or_default();
+                match value.as_i64().and_then(timestamp_date) {
+                    Some(date) => format!("{text}\n{date}"),
+                    None => text,
+                }
+            }
+            None => String::new(),
+        }
+    }
+
+    /// Decodes the selected string once if it is URL-encoded, for the detail pane
+    fn cache_url_decoded(&mut self) {
+        let path = document_path(&self.state.selected()).into_owned();
+        if self.url_decoded.contains_key(&path) {
+            return;
+        }
+        if let Some(decoded) = get_value_at_path(&self.root, &path)
+            .and_then(Value::as_str)
+            .and_then(url_decode)
+        {
+            self.url_decoded.insert(path, decoded);
+        }
+    }
+
+    /// Selected row of the tree and the lists
+    fn highlight_style(&self) -> Style {
+        Style::new()
+            .fg(self.colors.highlight_fg)
+            .bg(self.colors.highlight_bg)
+    }
+
+    /// File name of the document in the tab bar, the source as is for other inputs
+    fn tab_label(&self) -> String {
+        let name = match &self.source {
+            InputSource::File(path) => path.file_name().map_or_else(
+                || path.display().to_string(),
+                |name| name.to_string_lossy().into(),
+            ),
+            source => source.to_string(),
+        };
+        if self.filter.is_some() {
+            format!("{name} |")
+        } else {
+            name
+        }
+    }
+
+    fn selected_pointer(&self) -> String {
+        selected_json_pointer(&self.state.selected())
+    }
+
+    fn rebuild_items(&mut self) {
+        self.built_opened = self.state.get_all_opened().into_iter().collect();
+        let search = &self.search;
+        let diff = &self.diff;
+        let violations = &self.violations;
+        let style = |path: &[JsonPointer]| {
+            let style = if !search.is_active() {
+                Style::default()
+            } else if search.is_match(path) {
+                Style::new().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else if search.contains_match(path) {
+                Style::default()
+            } else {
+                Style::new().add_modifier(Modifier::DIM)
+            };
+            match diff.get(path) {
+                Some(mark) => Style::new().fg(mark.color).patch(style),
+                None if violations.contains_key(path) => Style::new().fg(Color::Red).patch(style),
+                None => style,
+            }
+        };
+        let nested = &self.nested;
+        let decoded = &self.decoded;
+        let url_decoded = &self.url_decoded;
+        let url_shown = &self.url_shown;
+        let bookmarked = &self.bookmarked;
+        let annotation = |path: &[JsonPointer]| match search.score(path) {
+            Some(score) => Some(format!("({score})")),
+            None => diff
+                .get(path)
+                .and_then(|mark| mark.other.as_ref())
+                .map(|other| format!("≠ {other}"))
+                .or_else(|| {
+                    violations
+                        .get(path)
+                        .map(|messages| format!("✗ {}", messages.join("; ")))
+                })
+                .or_else(|| {
+                    nested
+                        .get(path)
+                        .map(|(embedding, _)| embedding.marker().to_string())
+                })
+                .or_else(|| {
+                    decoded
+                        .contains_key(path)
+                        .then(|| Embedding::Base64.marker().to_string())
+                })
+                .or_else(|| {
+                    url_shown
+                        .contains(path)
+                        .then(|| Embedding::Url.marker().to_string())
+                })
+                .or_else(|| {
+                    bookmarked
+                        .contains(path)
+                        .then(|| BOOKMARK_MARKER.to_string())
+                }),
+        };
+        let value_text = |path: &[JsonPointer]| {
+            decoded.get(path).map(DecodedValue::summary).or_else(|| {
+                url_shown
+                    .contains(path)
+                    .then(|| url_decoded.get(path))
+                    .flatten()
+                    .map(|text| Value::from(text.as_str()).to_string())
+            })
+        };
+        let mut builder = ItemsBuilder::new(&style)
+            .sort(self.sort)
+            .colors(self.colors)
+            .timestamps_as_dates(self.show_timestamps_as_dates)
+            .number_format(self.number_format)
+            .preview(self.preview_chars);
+        if search.fuzzy
+            || !diff.is_empty()
+            || !nested.is_empty()
+            || !decoded.is_empty()
+            || !url_shown.is_empty()
+            || !violations.is_empty()
+            || !bookmarked.is_empty()
+        {
+            builder = builder.annotation(&annotation);
+        }
+        if !decoded.is_empty() || !url_shown.is_empty() {
+            builder = builder.value_text(&value_text);
+        }
+        let highlight = |text: &str| search.highlights(text);
+        if search.is_active() && !search.fuzzy {
+            builder = builder.highlight(&highlight);
+        }
+        let narrow = &self.narrow;
+        let visible = |path: &[JsonPointer]| {
+            narrow.is_match(path)
+                || narrow.contains_match(path)
+                || (1..path.len()).any(|len| narrow.is_match(&path[..len]))
+        };
+        if narrow.is_active() {
+            builder = builder.visible(&visible);
+        }
+        let lazy_document = &self.lazy_document;
+        let unloaded = |path: &[JsonPointer]| {
+            lazy_document
+                .as_ref()
+                .is_some_and(|document| document.is_unloaded(path))
+        };
+        if lazy_document.is_some() {
+            builder = builder.unloaded(&unloaded);
+        }
+        builder = builder
+            .opened(&self.built_opened)
+            .lazy(self.lazy)
+            .page_size(self.page_size);
+        self.items = builder.build(&self.root);
+        self.paged = builder.paginated();
+    }
+
+    /// Expands the selected string holding JSON into child nodes, or puts back the original string
+    fn toggle_nested(&mut self) -> bool {
+        let selected = self.state.selected();
+        let path = document_path(&selected).into_owned();
+        let Some(value) = get_value_at_path_mut(&mut self.root, &path) else {
+            return false;
+        };
+        if collapse_at(value, &path, &mut self.nested) {
+            self.state.close(&selected);
+            self.flash("Restored original string");
+        } else if expand_at(value, &path, &mut self.nested) {
+            self.state.open(selected);
+            self.flash("Expanded embedded JSON");
+        } else {
+            self.flash("Not a string holding a JSON object or array");
+            return true;
+        }
+        self.embedded_changed();
+        true
+    }
+
+    /// Switches the selected base64 string between encoded and decoded (text or hex dump)
+    /// view, JSON objects and arrays inside are expanded into child nodes instead
+    fn toggle_base64(&mut self) -> bool {
+        let selected = self.state.selected();
+        let path = document_path(&selected).into_owned();
+        if self.decoded.remove(&path).is_some() {
+            self.rebuild_items();
+            return true;
+        }
+        let Some(value) = get_value_at_path_mut(&mut self.root, &path) else {
+            return false;
+        };
+        if collapse_at(value, &path, &mut self.nested) {
+            self.state.close(&selected);
+            self.flash("Restored original string");
+            self.embedded_changed();
+            return true;
+        }
+        let Some(text) = value.as_str() else {
+            self.flash("Not a string");
+            return true;
+        };
+        let Some(decoded) = try_decode_base64(text) else {
+            self.flash("Not base64");
+            return true;
+        };
+        let embedded = match &decoded {
+            DecodedValue::Utf8(text) => parse_embedded(text),
+            DecodedValue::Hex(_) => None,
+        };
+        if let Some(embedded) = embedded {
+            replace_embedded(value, embedded, &path, Embedding::Base64, &mut self.nested);
+            self.state.open(selected);
+            self.flash("Expanded base64 JSON");
+            self.embedded_changed();
+        } else {
+            self.decoded.insert(path, decoded);
+            self.rebuild_items();
+        }
+        true
+    }
+
+    /// Switches the selected URL-encoded string between raw and decoded view,
+    /// JSON objects and arrays inside are expanded into child nodes instead
+    fn toggle_url_decoded(&mut self) -> bool {
+        let selected = self.state.selected();
+        let path = document_path(&selected).into_owned();
+        if self.url_shown.remove(&path) {
+            self.rebuild_items();
+            return true;
+        }
+        let Some(value) = get_value_at_path_mut(&mut self.root, &path) else {
+            return false;
+        };
+        if collapse_at(value, &path, &mut self.nested) {
+            self.state.close(&selected);
+            self.flash("Restored original string");
+            self.embedded_changed();
+            return true;
+        }
+        let Some(decoded) = value.as_str().and_then(url_decode) else {
+            self.flash("Not a URL-encoded string");
+            return true;
+        };
+        if let Some(embedded) = parse_embedded(&decoded) {
+            replace_embedded(value, embedded, &path, Embedding::Url, &mut self.nested);
+            self.state.open(selected);
+            self.flash("Expanded URL-encoded JSON");
+            self.embedded_changed();
+        } else {
+            self.url_decoded.insert(path.clone(), decoded);
+            self.url_shown.insert(path);
+            self.rebuild_items();
+        }
+        true
+    }
+
+    fn embedded_changed(&mut self) {
+        if self.search.is_active() {
+            self.search.update(&self.root);
+        }
+        self.rebuild_items();
+    }
+
+    fn toggle_timestamps(&mut self) -> bool {
+        self.show_timestamps_as_dates = !self.show_timestamps_as_dates;
+        self.rebuild_items();
+        self.flash(if self.show_timestamps_as_dates {
+            "Timestamps shown as dates"
+        } else {
+            "Timestamps shown as numbers"
+        });
+        true
+    }
+
+    fn cycle_number_format(&mut self) -> bool {
+        self.number_format = self.number_format.next();
+        self.rebuild_items();
+        self.flash(&format!("Numbers shown {}", self.number_format));
+        true
+    }
+
+    /// `o` cycles key ascending, descending and document order, `O` toggles value type order
+    fn toggle_sort(&mut self, by_type: bool) -> bool {
+        self.sort = match (by_type, self.sort) {
+            (true, SortMode::ValueType) => SortMode::Document,
+            (true, _) => SortMode::ValueType,
+            (false, SortMode::KeyAscending) => SortMode::KeyDescending,
+            (false, SortMode::KeyDescending) => SortMode::Document,
+            (false, _) => SortMode::KeyAscending,
+        };
+        self.rebuild_items();
+        self.flash(&format!("Sorted by {}", self.sort));
+        true
+    }
+
+    /// Reads the children of the opened nodes not read yet, of a document read a level at a time
+    fn load_opened(&mut self) {
+        let Some(document) = &self.lazy_document else {
+            return;
+        };
+        let mut opened: Vec<_> = self
+            .state
+            .get_all_opened()
+            .iter()
+            .map(|path| document_path(path).into_owned())
+            .filter(|path| document.is_unloaded(path))
+            .collect();
+        if opened.is_empty() {
+            return;
+        }
+        opened.sort_by_key(Vec::len);
+        for path in opened {
+            self.load_path(&path);
+        }
+        self.document_loaded();
+    }
+
+    /// Reads the objects and arrays on the way to `path` of a document read a level at a time
+    fn load_path(&mut self, path: &[JsonPointer]) {
+        if let Some(document) = &mut self.lazy_document {
+            if let Err(err) = document.load_path(&mut self.root, path) {
+                self.status = format!("Loading failed: {err}");
+            }
+        }
+    }
+
+    /// Reads all of the node at `path` of a document read a level at a time, for
+    /// copying, exporting, filtering or querying it
+    fn load_subtree(&mut self, path: &[JsonPointer]) -> Result<(), String> {
+        let Some(document) = &mut self.lazy_document else {
+            return Ok(());
+        };
+        if document.is_complete(path) {
+            return Ok(());
+        }
+        document
+            .load_subtree(&mut self.root, path)
+            .map_err(|err| format!("loading failed: {err}"))?;
+        if document.is_complete(&[]) {
+            self.lazy_document = None;
+        }
+        self.document_loaded();
+        Ok(())
+    }
+
+    /// Refreshes what is derived from the document once more of it was read
+    fn document_loaded(&mut self) {
+        self.path_index = None;
+        if self.search.is_active() {
+            self.search.update(&self.root);
+        }
+        if self.narrow.is_active() {
+            self.narrow.update(&self.root);
+        }
+        self.rebuild_items();
+    }
+
+    /// In lazy mode builds children of the nodes opened since the last build, with previews
+    /// rebuilds the items of nodes opened or closed
+    fn sync_lazy_items(&mut self) {
+        self.load_opened();
+        if (self.lazy || self.paged || self.preview_chars > 0)
+            && self
+                .state
+                .get_all_opened()
+                .into_iter()
+                .collect::<HashSet<_>>()
+                != self.built_opened
+        {
+            self.rebuild_items();
+        }
+    }
+
+    fn expand_all(&mut self) -> bool {
+        for path in opened_to_depth(&self.root, usize::MAX, self.page_size) {
+            self.state.open(path);
+        }
+        true
+    }
+
+    fn collapse_all(&mut self) -> bool {
+        self.state.close_all()
+    }
+
+    /// Shows the top `depth` levels of the document, deeper nodes stay collapsed
+    fn open_to_max_depth(&mut self, depth: usize) {
+        self.state.close_all();
+        loop {
+            let paths = opened_to_depth(&self.root, depth, self.page_size);
+            let count = paths.len();
+            for path in paths {
+                self.state.open(path);
+            }
+            self.sync_lazy_items();
+            // a document read a level at a time has the next level once this one is read
+            if self.lazy_document.is_none()
+                || opened_to_depth(&self.root, depth, self.page_size).len() == count
+            {
+                break;
+            }
+        }
+        // a restored selection deeper down moves up to its closest shown ancestor
+        let selected = self.state.selected();
+        let opened: HashSet<_> = self.state.get_all_opened().into_iter().collect();
+        let shown = (1..selected.len())
+            .take_while(|&len| opened.contains(&selected[..len]))
+            .count()
+            + 1;
+        if shown < selected.len() {
+            self.state.select(selected[..shown].to_vec());
+        }
+    }
+
+    /// Resolves the path, reading the objects and arrays on its way in a document read
+    /// a level at a time
+    fn resolve_loading(
+        &mut self,
+        segments: Vec<JsonPointer>,
+    ) -> Result<Vec<JsonPointer>, Unresolved> {
+        let mut loaded = false;
+        let resolved = loop {
+            match resolve(&self.root, segments.clone()) {
+                Err(unresolved)
+                    if self
+                        .lazy_document
+                        .as_ref()
+                        .is_some_and(|document| document.is_unloaded(&unresolved.resolved)) =>
+                {
+                    self.load_path(&unresolved.resolved);
+                    if self
+                        .lazy_document
+                        .as_ref()
+                        .is_some_and(|document| document.is_unloaded(&unresolved.resolved))
+                    {
+                        break Err(unresolved);
+                    }
+                    loaded = true;
+                }
+                resolved => break resolved,
+            }
+        };
+        if loaded {
+            self.document_loaded();
+        }
+        resolved
+    }
+
+    /// Selects the node at the document path, opening its ancestors
+    fn select_path(&mut self, path: &[JsonPointer]) {
+        let path = tree_path(&self.root, path, self.page_size);
+        for len in 1..path.len() {
+            self.state.open(path[..len].to_vec());
+        }
+        self.state.select(path.to_vec());
+    }
+
+    fn start_search(&mut self) -> bool {
+        self.search.editing = true;
+        self.search_origin = Some((
+            self.state.get_all_opened(),
+            self.state.selected(),
+            self.state.get_offset(),
+        ));
+        true
+    }
+
+    /// Searches only the descendants of the selected node, until the search is cleared
+    fn start_subtree_search(&mut self) -> bool {
+        let selected = document_path(&self.state.selected()).into_owned();
+        match get_value_at_path(&self.root, &selected) {
+            Some(Value::Object(_) | Value::Array(_)) => {}
+            _ => {
+                self.flash("Only objects and arrays can be searched in");
+                return true;
+            }
+        }
+        self.start_search();
+        self.search.subtree = (!selected.is_empty()).then_some(selected);
+        if self.search.is_active() {
+            self.update_search();
+        }
+        true
+    }
+
+    fn start_narrow(&mut self) -> bool {
+        if self.narrow_origin.is_none() {
+            self.narrow_origin = Some((
+                self.state.get_all_opened(),
+                self.state.selected(),
+                self.state.get_offset(),
+            ));
+        }
+        self.narrow.ignore_case = self.search.ignore_case;
+        self.narrow_input = Some(self.narrow.query.clone());
+        true
+    }
+
+    /// The tree narrows while typing, Enter keeps the filter, Esc clears it
+    fn narrow_input(&mut self, code: KeyCode) -> bool {
+        let Some(input) = &mut self.narrow_input else {
+            return false;
+        };
+        match code {
+            KeyCode::Enter => {
+                if self.narrow_pending.is_some() {
+                    self.apply_narrow();
+                }
+                self.narrow_input = None;
+                if !self.narrow.is_active() {
+                    self.narrow_origin = None;
+                }
+                return true;
+            }
+            KeyCode::Esc => return self.clear_narrow(),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => return false,
+        }
+        if self.lazy {
+            self.narrow_pending = Some(Instant::now());
+        } else {
+            self.apply_narrow();
+        }
+        true
+    }
+
+    /// Applies the live filter typed into once typing paused
+    fn apply_pending_narrow(&mut self) {
+        if self
+            .narrow_pending
+            .is_some_and(|typed| typed.elapsed() >= NARROW_DEBOUNCE)
+        {
+            self.apply_narrow();
+        }
+    }
+
+    /// Shows only the nodes matching the prompt, their ancestors opened, or the tree as it was
+    /// before the filter when the prompt is empty
+    fn apply_narrow(&mut self) {
+        self.narrow_pending = None;
+        self.narrow.query = self.narrow_input.clone().unwrap_or_default();
+        if !self.narrow.is_active() {
+            self.narrow.clear();
+            self.rebuild_items();
+            if let Some((opened, selected, offset)) = self.narrow_origin.clone() {
+                self.state = TreeState::default();
+                self.restore(opened, selected, offset);
+            }
+            return;
+        }
+        self.narrow.update(&self.root);
+        self.state = TreeState::default();
+        for path in self.narrow.matches.iter().take(MAX_NARROW_OPENED) {
+            let path = tree_path(&self.root, path, self.page_size);
+            for len in 1..path.len() {
+                self.state.open(path[..len].to_vec());
+            }
+        }
+        self.rebuild_items();
+        if let Some(path) = self.narrow.matches.first().cloned() {
+            self.select_path(&path);
+        }
+    }
+
+    /// Shows the whole tree again, opened and selected as before the filter
+    fn clear_narrow(&mut self) -> bool {
+        self.narrow_input = None;
+        self.narrow_pending = None;
+        self.narrow.clear();
+        self.rebuild_items();
+        if let Some((opened, selected, offset)) = self.narrow_origin.take() {
+            self.state = TreeState::default();
+            self.restore(opened, selected, offset);
+        }
+        true
+    }
+
+    /// Matches are selected while typing, Enter stays on the match, Esc puts the tree back
+    fn search_input(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Enter => {
+                self.search.editing = false;
+                self.search_origin = None;
+                if let Err(err) = self.search_history.commit(&self.search.query) {
+                    self.status = format!("Search history not saved: {err}");
+                }
+            }
+            KeyCode::Up | KeyCode::Down => {
+                let recalled = if code == KeyCode::Up {
+                    self.search_history.older(&self.search.query)
+                } else {
+                    self.search_history.newer()
+                };
+                if let Some(query) = recalled {
+                    self.search.query = query;
+                    self.update_search();
+                }
+            }
+            KeyCode::Esc => {
+                self.search_history.reset();
+                self.clear_search();
+                if let Some((opened, selected, offset)) = self.search_origin.take() {
+                    self.state = TreeState::default();
+                    self.restore(opened, selected, offset);
+                }
+            }
+            KeyCode::Backspace => {
+                self.search_history.reset();
+                self.search.query.pop();
+                self.update_search();
+            }
+            KeyCode::Char(c) => {
+                self.search_history.reset();
+                self.search.query.push(c);
+                self.update_search();
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn goto_input(&mut self, code: KeyCode) -> bool {
+        let Some(input) = &mut self.goto_input else {
+            return false;
+        };
+        match code {
+            KeyCode::Enter => {
+                let text = input.clone();
+                self.goto_input = None;
+                match parse_path(&text).map(|segments| self.resolve_loading(segments)) {
+                    Ok(Ok(path)) => {
+                        self.select_path(&path);
+                        self.status.clear();
+                    }
+                    // stops at the deepest node that exists
+                    Ok(Err(unresolved)) => {
+                        self.select_path(&unresolved.resolved);
+                        self.status = unresolved.message;
+                    }
+                    Err(err) => self.status = format!("Bad path {text}: {err}"),
+                }
+            }
+            KeyCode::Esc => self.goto_input = None,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => return false,
+        }
+        true
+    }
+
+    fn query_input(&mut self, code: KeyCode) -> bool {
+        let Some(input) = &mut self.query_input else {
+            return false;
+        };
+        match code {
+            KeyCode::Enter => {
+                let expr = input.clone();
+                if let Err(err) = self.load_subtree(&[]) {
+                    self.query_error = Some(err);
+                    return true;
+                }
+                match run_jsonpath(&self.root, &expr) {
+                    Ok(results) if results.is_empty() => {
+                        self.query_input = None;
+                        self.flash(&format!("No results: {expr}"));
+                    }
+                    Ok(results) => {
+                        self.query_input = None;
+                        self.query_state = ListState::default().with_selected(Some(0));
+                        self.query_results = Some(results);
+                    }
+                    // the prompt stays open to fix the expression
+                    Err(err) => self.query_error = Some(err),
+                }
+            }
+            KeyCode::Esc => {
+                self.query_input = None;
+                self.query_error = None;
+            }
+            KeyCode::Backspace => {
+                input.pop();
+                self.query_error = None;
+            }
+            KeyCode::Char(c) => {
+                input.push(c);
+                self.query_error = None;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn filter_input(&mut self, code: KeyCode) -> bool {
+        let Some(input) = &mut self.filter_input else {
+            return false;
+        };
+        match code {
+            KeyCode::Enter => {
+                let program = input.trim().to_string();
+                self.filter_input = None;
+                if let Err(err) = self.apply_filter(&program) {
+                    self.error_popup = Some(("jq filter failed", err));
+                }
+            }
+            KeyCode::Esc => self.filter_input = None,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => return false,
+        }
+        true
+    }
+
+    fn pipe_input(&mut self, code: KeyCode) -> bool {
+        let Some(input) = &mut self.pipe_input else {
+            return false;
+        };
+        match code {
+            KeyCode::Enter => {
+                let command = input.trim().to_string();
+                self.pipe_input = None;
+                let selected = document_path(&self.state.selected()).into_owned();
+                if command.is_empty() {
+                    return true;
+                }
+                if let Err(err) = self.load_subtree(&selected) {
+                    self.error_popup = Some(("Command failed", err));
+                    return true;
+                }
+                let Some(value) = get_value_at_path(&self.root, &selected) else {
+                    return true;
+                };
+                match pipe_to_command(value, &command) {
+                    Ok(output) => {
+                        self.pipe_output = Some((command, output));
+                        self.pipe_scroll = 0;
+                    }
+                    Err(err) => self.error_popup = Some(("Command failed", err)),
+                }
+            }
+            KeyCode::Esc => self.pipe_input = None,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Shows the selected leaf full-screen, objects and arrays are refused
+    fn show_fullscreen(&mut self) -> bool {
+        let path = document_path(&self.state.selected()).into_owned();
+        match get_value_at_path(&self.root, &path) {
+            Some(Value::Object(_) | Value::Array(_)) => {
+                self.flash("Only leaf values are shown full-screen")
+            }
+            Some(_) => self.view = ViewMode::FullscreenValue { scroll: 0 },
+            None => return false,
+        }
+        true
+    }
+
+    /// Scrolls the full-screen value, the end is clamped when rendering
+    fn fullscreen_input(&mut self, code: KeyCode) -> bool {
+        let ViewMode::FullscreenValue { scroll } = &mut self.view else {
+            return false;
+        };
+        match code {
+            KeyCode::Down | KeyCode::Char('j') => *scroll = scroll.saturating_add(1),
+            KeyCode::Up | KeyCode::Char('k') => *scroll = scroll.saturating_sub(1),
+            KeyCode::PageDown => *scroll = scroll.saturating_add(10),
+            KeyCode::PageUp => *scroll = scroll.saturating_sub(10),
+            KeyCode::Home => *scroll = 0,
+            KeyCode::End => *scroll = u16::MAX,
+            KeyCode::Esc | KeyCode::Char('q') => self.view = ViewMode::Tree,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Scrolls the output of the piped command, Esc closes it
+    fn pipe_output_input(&mut self, code: KeyCode) -> bool {
+        let Some((_, output)) = &self.pipe_output else {
+            return false;
+        };
+        let last = output.lines().count().saturating_sub(1) as u16;
+        match code {
+            KeyCode::Down => self.pipe_scroll = (self.pipe_scroll + 1).min(last),
+            KeyCode::Up => self.pipe_scroll = self.pipe_scroll.saturating_sub(1),
+            KeyCode::PageDown => self.pipe_scroll = (self.pipe_scroll + 10).min(last),
+            KeyCode::PageUp => self.pipe_scroll = self.pipe_scroll.saturating_sub(10),
+            KeyCode::Home => self.pipe_scroll = 0,
+            KeyCode::End => self.pipe_scroll = last,
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => self.pipe_output = None,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Shows the document through jq `program`, piped after the filter already applied.
+    /// An empty program goes back to the original document.
+    fn apply_filter(&mut self, program: &str) -> Result<(), String> {
+        if program.is_empty() {
+            if let Some(original) = self.unfiltered.take() {
+                self.filter = None;
+                self.title = self.source.to_string();
+                self.replace_root(original);
+                self.flash("Filter removed");
+            }
+            return Ok(());
+        }
+        let program = match &self.filter {
+            Some(applied) => format!("{applied} | {program}"),
+            None => program.to_string(),
+        };
+        self.load_subtree(&[])?;
+        let filtered = run_jq(self.unfiltered.as_ref().unwrap_or(&self.root), &program)?;
+        if self.unfiltered.is_none() {
+            self.unfiltered = Some(std::mem::take(&mut self.root));
+        }
+        self.title = format!("{} | {program}", self.source);
+        self.filter = Some(program);
+        self.replace_root(filtered);
+        self.flash("Filtered, | with empty filter shows the original");
+        Ok(())
+    }
+
+    /// Finds the selected node in the source text when the selection changed, filtered
+    /// documents have no source
+    fn update_location(&mut self) {
+        let selected = self.state.selected();
+        if matches!(&self.location, Some((path, _)) if *path == selected) {
+            return;
+        }
+        let position = match (&self.source_text, &self.filter) {
+            (Some(text), None) => locate(text, &selected).map(|offset| line_col(text, offset)),
+            _ => None,
+        };
+        self.location = Some((selected, position));
+    }
+
+    /// Opens the fuzzy finder over the paths of all nodes
+    fn open_finder(&mut self) -> bool {
+        let index = self
+            .path_index
+            .get_or_insert_with(|| PathIndex::build(&self.root, MAX_INDEXED_PATHS));
+        if index.truncated {
+            self.status = format!("Only the first {MAX_INDEXED_PATHS} paths can be found");
+        }
+        self.finder_input = Some(String::new());
+        self.update_finder();
+        true
+    }
+
+    fn update_finder(&mut self) {
+        let (Some(index), Some(input)) = (&self.path_index, &self.finder_input) else {
+            return;
+        };
+        self.finder_results = index.rank(input);
+        let selected = (!self.finder_results.is_empty()).then_some(0);
+        self.finder_state = ListState::default().with_selected(selected);
+    }
+
+    fn finder_input(&mut self, code: KeyCode) -> bool {
+        let Some(input) = &mut self.finder_input else {
+            return false;
+        };
+        let count = self.finder_results.len();
+        match code {
+            KeyCode::Down if count > 0 => select_cycled(&mut self.finder_state, count, true),
+            KeyCode::Up if count > 0 => select_cycled(&mut self.finder_state, count, false),
+            KeyCode::Enter => {
+                let path = self
+                    .finder_state
+                    .selected()
+                    .and_then(|selected| self.finder_results.get(selected))
+                    .zip(self.path_index.as_ref())
+                    .map(|(&entry, index)| index.path(entry).to_vec());
+                self.finder_input = None;
+                if let Some(path) = path {
+                    self.select_path(&path);
+                }
+            }
+            KeyCode::Esc => self.finder_input = None,
+            KeyCode::Backspace => {
+                input.pop();
+                self.update_finder();
+            }
+            KeyCode::Char(c) => {
+                input.push(c);
+                self.update_finder();
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn query_results_input(&mut self, code: KeyCode) -> bool {
+        let Some(results) = &self.query_results else {
+            return false;
+        };
+        let count = results.len();
+        match code {
+            KeyCode::Down => select_cycled(&mut self.query_state, count, true),
+            KeyCode::Up => select_cycled(&mut self.query_state, count, false),
+            KeyCode::Enter => {
+                let path = self
+                    .query_state
+                    .selected()
+                    .and_then(|index| results.get(index))
+                    .map(|result| result.path.clone());
+                self.query_results = None;
+                if let Some(path) = path {
+                    self.select_path(&path);
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') => self.query_results = None,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Bookmark of the selected node
+    fn selected_bookmark(&self) -> Option<Bookmark> {
+        let path = document_path(&self.state.selected()).into_owned();
+        let value = get_value_at_path(&self.root, &path)?;
+        Some(Bookmark {
+            label: scalar_text(value)
+                .map(|text| text.chars().take(BOOKMARK_LABEL_LEN).collect())
+                .unwrap_or_else(|| JsonType::of(value).to_string()),
+            path,
+            created_at: SystemTime::now(),
+        })
+    }
+
+    fn add_bookmark(&mut self) -> bool {
+        let InputSource::File(document) = &self.source else {
+            self.flash("Only nodes of files can be bookmarked");
+            return true;
+        };
+        let Some(bookmark) = self.selected_bookmark() else {
+            return false;
+        };
+        let mut store = BookmarkStore::load();
+        if !store.add(document, bookmark) {
+            self.flash("Already bookmarked");
+            return true;
+        }
+        let saved = store.save();
+        self.update_bookmarked(&store);
+        match saved {
+            Ok(()) => self.flash("Bookmarked"),
+            Err(err) => self.status = format!("Bookmark failed: {err}"),
+        }
+        true
+    }
+
+    /// Bookmarks the selected node, or removes its bookmark
+    fn toggle_bookmark(&mut self) -> bool {
+        let InputSource::File(document) = &self.source else {
+            self.flash("Only nodes of files can be bookmarked");
+            return true;
+        };
+        let Some(bookmark) = self.selected_bookmark() else {
+            return false;
+        };
+        let mut store = BookmarkStore::load();
+        let added = store.toggle(document, bookmark);
+        let saved = store.save();
+        self.update_bookmarked(&store);
+        match saved {
+            Ok(()) if added => self.flash("Bookmarked"),
+            Ok(()) => self.flash("Bookmark removed"),
+            Err(err) => self.status = format!("Bookmark failed: {err}"),
+        }
+        true
+    }
 
-impl ToString for JsonPointer {
-    fn to_string(&self) -> String {
-        match self {
-            Self::ObjectKey(key) => key.clone(),
-            Self::ArrayIdx(index) => index.to_string(),
-            Self::None => String::new(),
+    /// Marks the stored bookmarks of the document in the tree
+    fn update_bookmarked(&mut self, store: &BookmarkStore) {
+        let InputSource::File(document) = &self.source else {
+            return;
+        };
+        self.bookmarked = store.paths(document);
+        self.rebuild_items();
+    }
+
+    /// Selects the next or previous bookmark in the order they were added, opening its
+    /// ancestors. Bookmarks of paths missing from the document are skipped.
+    fn cycle_bookmark(&mut self, forward: bool) -> bool {
+        let InputSource::File(document) = &self.source else {
+            self.flash("Only nodes of files can be bookmarked");
+            return true;
+        };
+        let paths: Vec<_> = BookmarkStore::load()
+            .get(document)
+            .into_iter()
+            .map(|bookmark| bookmark.path)
+            .filter(|path| get_value_at_path(&self.root, path).is_some())
+            .collect();
+        if paths.is_empty() {
+            self.flash("No bookmarks, m adds one");
+            return true;
         }
+        let selected = document_path(&self.state.selected()).into_owned();
+        let next = match paths.iter().position(|path| *path == selected) {
+            Some(index) if forward => (index + 1) % paths.len(),
+            Some(index) => (index + paths.len() - 1) % paths.len(),
+            None if forward => 0,
+            None => paths.len() - 1,
+        };
+        self.select_path(&paths[next]);
+        self.flash(&format!("Bookmark {}/{}", next + 1, paths.len()));
+        true
     }
-}
 
-// TODO: https://github.com/aweinstock314/rust-clipboard
+    fn show_bookmarks(&mut self) -> bool {
+        let InputSource::File(document) = &self.source else {
+            self.flash("Only nodes of files can be bookmarked");
+            return true;
+        };
+        let bookmarks = BookmarkStore::load().get(document);
+        if bookmarks.is_empty() {
+            self.flash("No bookmarks, b adds one");
+            return true;
+        }
+        self.bookmark_state = ListState::default().with_selected(Some(0));
+        self.bookmarks = Some(bookmarks);
+        true
+    }
 
-struct Content {
-    key: Vec<JsonPointer>,
-    value: String,
-}
+    /// Enter goes to the bookmarked node, Delete removes the bookmark
+    fn bookmarks_input(&mut self, code: KeyCode) -> bool {
+        let Some(bookmarks) = &mut self.bookmarks else {
+            return false;
+        };
+        let count = bookmarks.len();
+        let selected = self
+            .bookmark_state
+            .selected()
+            .filter(|index| *index < count);
+        match code {
+            KeyCode::Down => select_cycled(&mut self.bookmark_state, count, true),
+            KeyCode::Up => select_cycled(&mut self.bookmark_state, count, false),
+            KeyCode::Enter => {
+                let path = selected.map(|index| bookmarks[index].path.clone());
+                self.bookmarks = None;
+                match path {
+                    Some(path) if get_value_at_path(&self.root, &path).is_some() => {
+                        self.select_path(&path)
+                    }
+                    Some(path) => {
+                        self.flash(&format!("No such path: {}", selected_json_pointer(&path)))
+                    }
+                    None => {}
+                }
+            }
+            KeyCode::Delete => {
+                let (Some(index), InputSource::File(document)) = (selected, &self.source) else {
+                    return false;
+                };
+                let removed = bookmarks.remove(index);
+                let mut store = BookmarkStore::load();
+                store.remove(document, &removed.path);
+                if let Err(err) = store.save() {
+                    self.status = format!("Bookmark removal failed: {err}");
+                }
+                if bookmarks.is_empty() {
+                    self.bookmarks = None;
+                } else {
+                    self.bookmark_state
+                        .select(Some(index.min(bookmarks.len() - 1)));
+                }
+                self.update_bookmarked(&store);
+            }
+            KeyCode::Esc | KeyCode::Char('q') => self.bookmarks = None,
+            _ => return false,
+        }
+        true
+    }
 
-impl fmt::Debug for Content {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_tuple("")
-            .field(&self.key)
-            .field(&self.value)
-            .finish()
+    fn export_input(&mut self, code: KeyCode) -> bool {
+        let Some(input) = &mut self.export_input else {
+            return false;
+        };
+        match code {
+            KeyCode::Enter => {
+                let path = PathBuf::from(input.as_str());
+                self.export_input = None;
+                match self.export_selected(&path) {
+                    Ok(()) => self.flash(&format!("Exported to {}", path.display())),
+                    Err(err) => self.status = format!("Export failed: {err}"),
+                }
+            }
+            KeyCode::Esc => self.export_input = None,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Writes the tree as shown to `tree-<date>-<time>.txt` in the current directory
+    fn write_tree_text(&mut self) -> bool {
+        let text = render_tree_to_text(&self.root, &self.state, &self.items);
+        let time = DateTime::<Utc>::from(SystemTime::now()).format("%Y%m%d-%H%M%S");
+        let file = format!("tree-{time}.txt");
+        match fs::write(&file, text) {
+            Ok(()) => self.flash(&format!("Tree written to {file}")),
+            Err(err) => self.status = format!("Writing {file} failed: {err}"),
+        }
+        true
+    }
+
+    /// Starts editing the selected leaf, pre-populated with its JSON text
+    fn start_edit(&mut self) -> bool {
+        let path = self.state.selected();
+        match get_value_at_path(&self.root, &path) {
+            Some(Value::Object(_) | Value::Array(_)) => {
+                self.flash("Only leaf values can be edited")
+            }
+            Some(value) => {
+                self.edit_state = Some(EditState {
+                    input: Input::new(value.to_string()),
+                    path,
+                    error: None,
+                });
+            }
+            None => {}
+        }
+        true
+    }
+
+    fn edit_input(&mut self, key: event::KeyEvent) -> bool {
+        let Some(edit) = &mut self.edit_state else {
+            return false;
+        };
+        match key.code {
+            KeyCode::Enter => match serde_json::from_str::<Value>(edit.input.value()) {
+                Ok(value) => {
+                    let path = document_path(&edit.path).into_owned();
+                    self.edit_state = None;
+                    if let Some(target) = get_value_at_path_mut(&mut self.root, &path) {
+                        let old = std::mem::replace(target, value.clone());
+                        self.undo.push(EditOp {
+                            path: path.clone(),
+                            old,
+                            new: value,
+                        });
+                    }
+                    self.decoded.remove(&path);
+                    self.url_decoded.remove(&path);
+                    self.url_shown.remove(&path);
+                    self.document_edited();
+                    self.flash("Value updated");
+                }
+                Err(err) => edit.error = Some(err.to_string()),
+            },
+            KeyCode::Esc => self.edit_state = None,
+            _ => {
+                edit.error = None;
+                edit.input.handle_event(&Event::Key(key));
+            }
+        }
+        true
+    }
+
+    /// Refreshes what depends on the values after an edit, saving the edits with the session
+    fn document_edited(&mut self) {
+        self.path_index = None;
+        self.check_schema();
+        if self.search.is_active() {
+            self.search.update(&self.root);
+        }
+        self.rebuild_items();
+        self.write_session(self.undo.clone());
+    }
+
+    /// `Ctrl+Z` reverts the last edit and selects the value
+    fn undo_edit(&mut self) -> bool {
+        match self.undo.undo(&mut self.root) {
+            Some(path) => self.edit_reverted(path, "Edit undone"),
+            None => self.flash("Nothing to undo"),
+        }
+        true
+    }
+
+    /// `Ctrl+Y` applies the last undone edit again
+    fn redo_edit(&mut self) -> bool {
+        match self.undo.redo(&mut self.root) {
+            Some(path) => self.edit_reverted(path, "Edit redone"),
+            None => self.flash("Nothing to redo"),
+        }
+        true
+    }
+
+    fn edit_reverted(&mut self, path: Vec<JsonPointer>, message: &str) {
+        self.decoded.remove(&path);
+        self.url_decoded.remove(&path);
+        self.url_shown.remove(&path);
+        self.document_edited();
+        self.select_path(&path);
+        self.flash(message);
+    }
+
+    /// `p` switches printing of the whole (possibly edited) document on exit
+    fn toggle_print(&mut self) -> bool {
+        self.print_on_exit = match self.print_on_exit {
+            Some(ExitPrint::All) => None,
+            _ => Some(ExitPrint::All),
+        };
+        self.flash(match self.print_on_exit {
+            Some(_) => "Document will be printed on exit",
+            None => "Nothing will be printed on exit",
+        });
+        true
+    }
+
+    /// What to print once the viewer exits
+    fn exit_output(&mut self) -> Option<ExitOutput> {
+        let printed = match self.print_on_exit? {
+            ExitPrint::All => Vec::new(),
+            _ => document_path(&self.state.selected()).into_owned(),
+        };
+        if let Err(err) = self.load_subtree(&printed) {
+            return Some(ExitOutput::Text(err));
+        }
+        match self.print_on_exit? {
+            ExitPrint::All => Some(ExitOutput::Json(std::mem::take(&mut self.root))),
+            ExitPrint::Selected => get_value_at_path(&self.root, &self.state.selected())
+                .cloned()
+                .map(ExitOutput::Json),
+            ExitPrint::Path(format) => Some(ExitOutput::Text(format_path(
+                &document_path(&self.state.selected()),
+                format,
+            ))),
+        }
+    }
+
+    /// Enter with `--select` exits printing the path of the selected node
+    fn pick_path(&mut self, format: PathFormat) -> bool {
+        self.print_on_exit = Some(ExitPrint::Path(format));
+        self.quit = true;
+        true
+    }
+
+    /// Writes the selected subtree (or leaf value) as pretty-printed JSON
+    fn export_selected(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let selected = document_path(&self.state.selected()).into_owned();
+        self.load_subtree(&selected)?;
+        let value = get_value_at_path(&self.root, &selected).ok_or("nothing selected")?;
+        let mut writer = io::BufWriter::new(fs::File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, value)?;
+        writeln!(writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Switches between substring and fuzzy search, starting to edit the query
+    fn toggle_fuzzy(&mut self) -> bool {
+        self.search.fuzzy = !self.search.fuzzy;
+        if self.search.fuzzy && self.search.is_regex() {
+            self.search.toggle_regex();
+        }
+        if !self.search.editing {
+            self.start_search();
+        }
+        self.update_search();
+        true
+    }
+
+    /// Switches between plain and regex search, starting to edit the query
+    fn toggle_regex(&mut self) -> bool {
+        self.search.toggle_regex();
+        if !self.search.editing {
+            self.start_search();
+        }
+        self.update_search();
+        true
+    }
+
+    /// Switches plain and regex matching between case-sensitive and not, kept for later searches
+    fn toggle_ignore_case(&mut self) -> bool {
+        self.search.ignore_case = !self.search.ignore_case;
+        self.update_search();
+        true
+    }
+
+    /// Matches only keys or only values, or both again
+    fn toggle_search_scope(&mut self, scope: SearchScope) -> bool {
+        self.search.toggle_scope(scope);
+        self.update_search();
+        true
+    }
+
+    fn update_search(&mut self) {
+        self.search.update(&self.root);
+        self.results_state.select(self.search.current);
+        self.rebuild_items();
+        if let Some(path) = self.search.current_match().map(<[_]>::to_vec) {
+            self.select_path(&path);
+            self.show_match_context(&path);
+        }
+    }
+
+    /// Shows the text around the match when it is inside a long string value
+    fn show_match_context(&mut self, path: &[JsonPointer]) {
+        if let Some(Value::String(text)) = get_value_at_path(&self.root, path) {
+            if let Some(context) = self.search.match_context(text) {
+                self.status = context;
+                self.status_expires = None;
+            }
+        }
+    }
+
+    fn clear_search(&mut self) -> bool {
+        self.search.clear();
+        self.rebuild_items();
+        true
+    }
+
+    /// Selects the next or previous match, noting when the search went past the end
+    fn next_match(&mut self, forward: bool) -> bool {
+        let before = self.search.current;
+        let path = if forward {
+            self.search.next()
+        } else {
+            self.search.prev()
+        };
+        let Some(path) = path.map(<[_]>::to_vec) else {
+            return false;
+        };
+        self.select_path(&path);
+        self.results_state.select(self.search.current);
+        self.show_match_context(&path);
+        match (before, self.search.current) {
+            (Some(before), Some(after)) if forward && after < before => {
+                self.flash("Search wrapped to the top")
+            }
+            (Some(before), Some(after)) if !forward && after > before => {
+                self.flash("Search wrapped to the bottom")
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Selects the next or previous member anywhere in the document named like the selected one
+    fn same_key(&mut self, forward: bool) -> bool {
+        let selected = document_path(&self.state.selected()).into_owned();
+        let Some(JsonPointer::ObjectKey(key)) = selected.last() else {
+            self.flash("Only object members have a key to look for");
+            return true;
+        };
+        let found = find_key(&self.root, key);
+        let Some(index) = found.iter().position(|path| *path == selected) else {
+            return false;
+        };
+        if found.len() == 1 {
+            self.flash(&format!("No other '{key}' in the document"));
+            return true;
+        }
+        let (next, wrapped) = if forward {
+            ((index + 1) % found.len(), index + 1 == found.len())
+        } else {
+            ((index + found.len() - 1) % found.len(), index == 0)
+        };
+        let message = match (wrapped, forward) {
+            (true, true) => format!("'{key}' wrapped to the top"),
+            (true, false) => format!("'{key}' wrapped to the bottom"),
+            (false, _) => format!("'{key}' {}/{}", next + 1, found.len()),
+        };
+        self.select_path(&found[next]);
+        self.flash(&message);
+        true
+    }
+
+    fn search_status(&self) -> String {
+        let status = match self.search.current {
+            Some(index) if self.search.fuzzy => {
+                let score = self.search.score(&self.search.matches[index]).unwrap_or(0);
+                format!(
+                    "match {}/{} score {score}",
+                    index + 1,
+                    self.search.matches.len()
+                )
+            }
+            Some(index) => format!("match {}/{}", index + 1, self.search.matches.len()),
+            None if self.search.is_active() => "no matches".to_string(),
+            None => String::new(),
+        };
+        match self.search.scope {
+            SearchScope::Keys if self.search.is_active() => format!("{status} in keys"),
+            SearchScope::Values if self.search.is_active() => format!("{status} in values"),
+            _ => status,
+        }
     }
 }
 
-struct App<'a> {
-    state: TreeState<JsonPointer>,
-    items: Vec<TreeItem<'a, JsonPointer>>,
-    show_cmd_popup: bool,
+struct TabManager<'a> {
+    tabs: Vec<App<'a>>,
+    active: usize,
+    /// First two tabs are shown side by side (diff mode)
+    split: bool,
 }
 
-impl<'a> App<'a> {
-    fn new(items: Vec<TreeItem<'a, JsonPointer>>) -> Self {
+impl<'a> TabManager<'a> {
+    fn new(tabs: Vec<App<'a>>) -> Self {
         Self {
-            state: TreeState::default(),
-            items,
-            show_cmd_popup: false,
+            tabs,
+            active: 0,
+            split: false,
+        }
+    }
+
+    fn active_mut(&mut self) -> &mut App<'a> {
+        &mut self.tabs[self.active]
+    }
+
+    fn select(&mut self, index: usize) -> bool {
+        if index >= self.tabs.len() {
+            return false;
+        }
+        self.active = index;
+        true
+    }
+
+    fn next(&mut self) -> bool {
+        self.select((self.active + 1) % self.tabs.len())
+    }
+
+    fn prev(&mut self) -> bool {
+        self.select((self.active + self.tabs.len() - 1) % self.tabs.len())
+    }
+
+    fn close(&mut self) -> bool {
+        self.tabs.remove(self.active).save_session();
+        self.active = self.active.min(self.tabs.len().saturating_sub(1));
+        true
+    }
+
+    fn run(&mut self, command: TabCommand) -> bool {
+        match command {
+            TabCommand::Next => self.next(),
+            TabCommand::Prev => self.prev(),
+            TabCommand::Close => self.close(),
+            TabCommand::Select(index) => self.select(index),
+        }
+    }
+
+    /// Shows the first two documents side by side with their differences highlighted
+    fn diff(&mut self) {
+        let diff = json_diff(&self.tabs[0].root, &self.tabs[1].root);
+        for (index, tab) in self.tabs.iter_mut().enumerate() {
+            tab.diff = diff.marks(index == 0);
+            tab.detail_width = 0;
+            tab.rebuild_items();
+        }
+        self.split = true;
+    }
+
+    fn save_sessions(&self) {
+        for tab in &self.tabs {
+            tab.save_session();
+        }
+    }
+
+    /// Tab bar over the tree, numbered for `1`..`9`, when there are several documents not shown side by side
+    fn tab_bar(&self) -> Option<Tabs<'static>> {
+        if self.tabs.len() < 2 || self.split {
+            return None;
         }
+        let titles = self
+            .tabs
+            .iter()
+            .enumerate()
+            .map(|(index, tab)| format!("{} {}", index + 1, tab.tab_label()));
+        let tabs = Tabs::new(titles)
+            .select(self.active)
+            .highlight_style(self.tabs[self.active].highlight_style());
+        Some(tabs)
     }
 }
 
-pub fn root_tree_items<'a>(root: &Value) -> Vec<TreeItem<'_, JsonPointer>> {
-    match root {
-        Value::Object(object) => tree_items_obj(object),
-        Value::Array(array) => tree_items_arr(array),
-        _ => vec![TreeItem::new_leaf(JsonPointer::None, root.to_string())],
+pub fn get_value_at_path<'v>(root: &'v Value, path: &[JsonPointer]) -> Option<&'v Value> {
+    path.iter().try_fold(root, |value, pointer| match pointer {
+        JsonPointer::ObjectKey(key) => value.get(key),
+        JsonPointer::ArrayIdx(index) => value.get(index),
+        JsonPointer::ArrayPage(..) | JsonPointer::None => Some(value),
+    })
+}
+
+pub fn get_value_at_path_mut<'v>(
+    root: &'v mut Value,
+    path: &[JsonPointer],
+) -> Option<&'v mut Value> {
+    path.iter().try_fold(root, |value, pointer| match pointer {
+        JsonPointer::ObjectKey(key) => value.get_mut(key),
+        JsonPointer::ArrayIdx(index) => value.get_mut(index),
+        JsonPointer::ArrayPage(..) | JsonPointer::None => Some(value),
+    })
+}
+
+const DEFAULT_DETAIL_WIDTH: u16 = 40;
+/// Rows of the search results pane, borders included
+const RESULTS_HEIGHT: u16 = 10;
+/// Pause in typing into the live filter before a large document gets narrowed
+const NARROW_DEBOUNCE: Duration = Duration::from_millis(300);
+/// Matches of the live filter opened to, the rest stay collapsed under their ancestors
+const MAX_NARROW_OPENED: usize = 1000;
+/// Matches listed in the results pane at most
+const MAX_LISTED_MATCHES: usize = 1000;
+/// Characters of the values listed in the results pane
+const RESULT_SNIPPET_LEN: usize = 60;
+const FLASH_DURATION: Duration = Duration::from_secs(3);
+/// Columns the tree moves by on horizontal scroll
+const H_SCROLL_STEP: u16 = 4;
+/// Columns past the right edge checked for text of truncated tree items
+const OVERFLOW_LOOKAHEAD: u16 = 16;
+/// Characters of the value kept as bookmark label
+const BOOKMARK_LABEL_LEN: usize = 60;
+/// Annotation of bookmarked nodes in the tree
+const BOOKMARK_MARKER: &str = "★";
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(300);
+/// Time the mouse rests on a truncated leaf before its full value shows up
+const HOVER_DELAY: Duration = Duration::from_millis(200);
+/// Widest tooltip, longer values are wrapped
+const TOOLTIP_WIDTH: u16 = 60;
+/// Documents bigger than this get tree items of children built only when expanded
+const LAZY_NODES: usize = 200_000;
+/// JSON files bigger than this are read a level at a time, objects and arrays once expanded
+const LAZY_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+fn focus_style(app: &App, pane: Pane) -> Style {
+    if (app.detail_width > 0 || app.results_open) && app.focus == pane {
+        Style::new().fg(Color::Cyan)
+    } else {
+        Style::new().fg(app.colors.border_color)
     }
 }
 
-fn tree_items(key: JsonPointer, value: &Value) -> TreeItem<JsonPointer> {
-    match value {
-        Value::Object(object) => {
-            let text = key.to_string();
-            TreeItem::new(key, text, tree_items_obj(object)).unwrap()
+fn render_tree(f: &mut Frame, app: &mut App, area: Rect, indicator: &str) {
+    let search = match app.search.current {
+        _ if !app.search.is_active() => String::new(),
+        Some(index) => format!(
+            " [Search: \"{}\"] {}/{} matches",
+            app.search.query,
+            index + 1,
+            app.search.matches.len()
+        ),
+        None => format!(" [Search: \"{}\"] no matches", app.search.query),
+    };
+    let narrow = match app.narrow.matches.len() {
+        _ if !app.narrow.is_active() => String::new(),
+        0 => format!(" [Filter: \"{}\"] no matches", app.narrow.query),
+        count => format!(" [Filter: \"{}\"] {count} matches", app.narrow.query),
+    };
+    let block = Block::bordered()
+        .title(format!(
+            "{} - {}{narrow}{search}{indicator}",
+            env!("CARGO_PKG_DESCRIPTION"),
+            app.title
+        ))
+        .border_style(focus_style(app, Pane::Tree));
+    let [breadcrumb, inner] =
+        Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(block.inner(area));
+    f.render_widget(block, area);
+    render_breadcrumb(f, breadcrumb, &app.state.selected());
+    let items = Tree::new(app.items.clone())
+        .expect("unique item identifiers")
+        .highlight_style(app.highlight_style().add_modifier(Modifier::BOLD));
+    // rendered off-screen wider by the horizontal scroll (plus a look-ahead for
+    // truncated items), then copied shifted to the left
+    let wide = Rect::new(
+        0,
+        0,
+        inner
+            .width
+            .saturating_add(app.h_scroll + OVERFLOW_LOOKAHEAD),
+        inner.height,
+    );
+    let mut buffer = Buffer::empty(wide);
+    StatefulWidget::render(items, wide, &mut buffer, &mut app.state);
+    let frame = f.buffer_mut();
+    app.truncated_rows.clear();
+    for y in 0..inner.height {
+        for x in 0..inner.width {
+            *frame.get_mut(inner.x + x, inner.y + y) = buffer.get(app.h_scroll + x, y).clone();
         }
-        Value::Array(array) => {
-            let text = key.to_string();
-            TreeItem::new(key, text, tree_items_arr(array)).unwrap()
+        let right = app.h_scroll + inner.width;
+        let truncated = (right..wide.width).any(|x| buffer.get(x, y).symbol() != " ");
+        if truncated && inner.width > 0 {
+            frame
+                .get_mut(inner.right() - 1, inner.y + y)
+                .set_symbol("›");
+            app.truncated_rows.insert(inner.y + y);
         }
-        _ => {
-            let text = format!("{}: {value}", key.to_string());
-            TreeItem::new_leaf(key, text)
+    }
+    app.breadcrumb_area = breadcrumb;
+    app.tree_area = inner;
+    render_edit(f, app, inner);
+}
+
+/// Path from the root to the node, e.g. `root > ticket > list > [0] > customer_id`,
+/// segments at even span indices
+fn breadcrumb_line(path: &[JsonPointer]) -> Line<'static> {
+    let segments = path.iter().filter_map(|pointer| match pointer {
+        JsonPointer::ObjectKey(key) => Some(key.clone()),
+        JsonPointer::ArrayIdx(index) => Some(format!("[{index}]")),
+        page @ JsonPointer::ArrayPage(..) => Some(page.to_string()),
+        JsonPointer::None => None,
+    });
+    let segments: Vec<String> = std::iter::once("root".to_string())
+        .chain(segments)
+        .collect();
+    let last = segments.len() - 1;
+    let mut spans = Vec::new();
+    for (index, segment) in segments.into_iter().enumerate() {
+        if index > 0 {
+            spans.push(" > ".dark_gray());
         }
+        spans.push(if index == last {
+            segment.bold()
+        } else {
+            segment.into()
+        });
     }
+    Line::from(spans)
 }
 
-fn tree_items_obj<'a>(object: &serde_json::Map<String, Value>) -> Vec<TreeItem<'_, JsonPointer>> {
-    assert!(object.len() < std::usize::MAX);
-    object
+/// Columns the breadcrumb is scrolled by, so that its end is visible
+fn breadcrumb_scroll(line: &Line, width: u16) -> u16 {
+    (line.width() as u16).saturating_sub(width)
+}
+
+fn render_breadcrumb(f: &mut Frame, area: Rect, path: &[JsonPointer]) {
+    let line = breadcrumb_line(path);
+    let scroll = breadcrumb_scroll(&line, area.width);
+    f.render_widget(Paragraph::new(line).scroll((0, scroll)), area);
+}
+
+/// Edit field over the row of the edited leaf, red while the text is rejected
+fn render_edit(f: &mut Frame, app: &App, inner: Rect) {
+    let Some(edit) = &app.edit_state else {
+        return;
+    };
+    let Some(index) = app
+        .state
+        .flatten(&app.items)
         .iter()
-        .map(|(key, value)| tree_items(JsonPointer::ObjectKey(key.clone()), value))
-        .collect()
+        .position(|flattened| flattened.identifier == edit.path)
+    else {
+        return;
+    };
+    let Some(row) = index
+        .checked_sub(app.state.get_offset())
+        .and_then(|row| u16::try_from(row).ok())
+        .filter(|row| *row < inner.height)
+    else {
+        return;
+    };
+    // text of a leaf starts after the indentation and the node symbol
+    let indent = (edit.path.len() as u16 * 2).saturating_sub(app.h_scroll);
+    let label = match edit.path.last() {
+        Some(key @ (JsonPointer::ObjectKey(_) | JsonPointer::ArrayIdx(_))) => format!("{key}: "),
+        _ => String::new(),
+    };
+    let x = inner.x + indent.min(inner.width);
+    let area = Rect::new(x, inner.y + row, inner.right() - x, 1);
+    let width = area.width.saturating_sub(label.len() as u16 + 1);
+    let scroll = edit.input.visual_scroll(width as usize);
+    let style = match edit.error {
+        Some(_) => Style::new().fg(Color::White).bg(Color::Red),
+        None => app.highlight_style(),
+    };
+    let line = Line::from(vec![
+        label.as_str().bold(),
+        Span::styled(
+            edit.input.value().chars().skip(scroll).collect::<String>(),
+            style,
+        ),
+    ]);
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(line).style(style), area);
+    f.set_cursor(
+        area.x + label.len() as u16 + (edit.input.visual_cursor() - scroll) as u16,
+        area.y,
+    );
+}
+
+fn render_detail(f: &mut Frame, app: &mut App, area: Rect) {
+    let selected = app.state.selected();
+    if selected != app.detail_path {
+        app.detail_path = selected;
+        app.detail_scroll = 0;
+    }
+    app.cache_url_decoded();
+    let paragraph = Paragraph::new(app.selected_detail())
+        .block(
+            Block::bordered()
+                .title("Value")
+                .border_style(focus_style(app, Pane::Detail)),
+        )
+        .wrap(Wrap { trim: false })
+        .scroll((app.detail_scroll, 0));
+    f.render_widget(paragraph, area);
+}
+
+fn render_commands(f: &mut Frame, app: &mut App, area: Rect) {
+    let block = Block::default()
+        .title("Available commands")
+        .borders(Borders::ALL);
+    let items = COMMANDS.iter().map(|command| {
+        Line::from(vec![
+            format!("{:<12}", command.key).bold(),
+            command.description.into(),
+        ])
+    });
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(app.highlight_style());
+    let area = centered_rect(60, 60, area);
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut app.cmd_state);
+}
+
+fn render_query_results(f: &mut Frame, app: &mut App, area: Rect) {
+    let Some(results) = &app.query_results else {
+        return;
+    };
+    let block = Block::default()
+        .title(format!("{} results (Enter to go to)", results.len()))
+        .borders(Borders::ALL);
+    let items = results.iter().map(|result| {
+        Line::from(vec![
+            selected_json_pointer(&result.path).bold(),
+            "  ".into(),
+            result.value.to_string().into(),
+        ])
+    });
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(app.highlight_style());
+    let area = centered_rect(80, 60, area);
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut app.query_state);
+}
+
+/// Search matches with their values, Enter selects one in the tree
+fn render_results(f: &mut Frame, app: &mut App, area: Rect) {
+    let matches = &app.search.matches;
+    let title = if !app.search.is_active() {
+        "Matches (search with / to list them, R to close)".to_string()
+    } else if matches.len() > MAX_LISTED_MATCHES {
+        format!("Matches: first {MAX_LISTED_MATCHES} of {}", matches.len())
+    } else {
+        format!("Matches: {} (Enter to go to, R to close)", matches.len())
+    };
+    let items = matches.iter().take(MAX_LISTED_MATCHES).map(|path| {
+        let snippet = match get_value_at_path(&app.root, path) {
+            Some(value @ (Value::Object(_) | Value::Array(_))) => {
+                compact_preview(value, RESULT_SNIPPET_LEN)
+            }
+            Some(value) => value.to_string().chars().take(RESULT_SNIPPET_LEN).collect(),
+            None => String::new(),
+        };
+        Line::from(vec![
+            selected_json_pointer(path).bold(),
+            "  ".into(),
+            snippet.dark_gray(),
+        ])
+    });
+    let list = List::new(items)
+        .block(
+            Block::bordered()
+                .title(title)
+                .border_style(focus_style(app, Pane::Results)),
+        )
+        .highlight_style(app.highlight_style());
+    f.render_stateful_widget(list, area, &mut app.results_state);
+}
+
+/// Value of the selected leaf over the whole tree area, scrolled no further than its last page
+fn render_fullscreen_value(f: &mut Frame, app: &mut App, area: Rect) {
+    if app.view == ViewMode::Tree {
+        return;
+    }
+    app.cache_url_decoded();
+    let text = app.selected_detail();
+    let title = format!("{} (Esc to close)", app.selected_pointer());
+    let ViewMode::FullscreenValue { scroll } = &mut app.view else {
+        return;
+    };
+    let width = usize::from(area.width.saturating_sub(2)).max(1);
+    let lines: usize = text
+        .lines()
+        .map(|line| line.chars().count().max(1).div_ceil(width))
+        .sum();
+    let last = lines.saturating_sub(usize::from(area.height.saturating_sub(2)));
+    *scroll = (*scroll).min(u16::try_from(last).unwrap_or(u16::MAX));
+    let paragraph = Paragraph::new(text)
+        .block(Block::bordered().title(title))
+        .wrap(Wrap { trim: false })
+        .scroll((*scroll, 0));
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+fn render_pipe_output(f: &mut Frame, app: &App, area: Rect) {
+    let Some((command, output)) = &app.pipe_output else {
+        return;
+    };
+    let block = Block::default()
+        .title(format!("! {command} (Esc to close)"))
+        .borders(Borders::ALL);
+    let text = if output.is_empty() {
+        "(no output)".dark_gray().into()
+    } else {
+        Text::from(output.as_str())
+    };
+    let paragraph = Paragraph::new(text)
+        .block(block)
+        .scroll((app.pipe_scroll, 0));
+    let area = centered_rect(80, 60, area);
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+/// Full value of the hovered truncated leaf, below the mouse or above it near the bottom
+fn render_tooltip(f: &mut Frame, app: &App, area: Rect) {
+    let Some((column, row, text)) = app.tooltip() else {
+        return;
+    };
+    let longest = text
+        .lines()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    let width = (longest as u16 + 2).clamp(3, TOOLTIP_WIDTH.min(area.width));
+    let inner_width = usize::from(width - 2).max(1);
+    let lines: usize = text
+        .lines()
+        .map(|line| line.chars().count().max(1).div_ceil(inner_width))
+        .sum();
+    let height = (lines.max(1) as u16 + 2).min(area.height);
+    let x = column.min(area.right().saturating_sub(width)).max(area.x);
+    let y = if row + 1 + height <= area.bottom() {
+        row + 1
+    } else {
+        row.saturating_sub(height).max(area.y)
+    };
+    let tooltip = Rect::new(x, y, width, height);
+    let paragraph = Paragraph::new(text)
+        .block(Block::bordered().border_style(Style::new().fg(Color::DarkGray)))
+        .wrap(Wrap { trim: false });
+    f.render_widget(Clear, tooltip);
+    f.render_widget(paragraph, tooltip);
 }
 
-fn tree_items_arr<'a>(array: &[Value]) -> Vec<TreeItem<'_, JsonPointer>> {
-    assert!(array.len() < std::usize::MAX);
-    array
+fn render_finder(f: &mut Frame, app: &mut App, area: Rect) {
+    let (Some(input), Some(index)) = (&app.finder_input, &app.path_index) else {
+        return;
+    };
+    let block = Block::default()
+        .title(format!(
+            "Find path: {input} ({} of {} paths)",
+            app.finder_results.len(),
+            index.len()
+        ))
+        .borders(Borders::ALL);
+    let items = app
+        .finder_results
         .iter()
-        .enumerate()
-        .map(|(index, value)| tree_items(JsonPointer::ArrayIdx(index), value))
-        .collect()
+        .map(|&entry| Line::from(index.text(entry)));
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(app.highlight_style());
+    let area = centered_rect(80, 60, area);
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut app.finder_state);
+}
+
+fn render_bookmarks(f: &mut Frame, app: &mut App, area: Rect) {
+    let Some(bookmarks) = &app.bookmarks else {
+        return;
+    };
+    let block = Block::default()
+        .title("Bookmarks (Enter to go to, Delete to remove)")
+        .borders(Borders::ALL);
+    // the value as it is now, the label kept when it was bookmarked once its path is gone
+    let items = bookmarks.iter().map(|bookmark| {
+        let preview = match get_value_at_path(&app.root, &bookmark.path) {
+            Some(value) => scalar_text(value)
+                .map(|text| text.chars().take(BOOKMARK_LABEL_LEN).collect())
+                .unwrap_or_else(|| compact_preview(value, BOOKMARK_LABEL_LEN)),
+            None => format!("{} (missing)", bookmark.label),
+        };
+        Line::from(vec![
+            selected_json_pointer(&bookmark.path).bold(),
+            "  ".into(),
+            preview.dark_gray(),
+        ])
+    });
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(app.highlight_style());
+    let area = centered_rect(80, 60, area);
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut app.bookmark_state);
+}
+
+/// Selects the next (or previous) of `count` list items, wrapping around
+fn select_cycled(state: &mut ListState, count: usize, forward: bool) {
+    let step = if forward { 1 } else { count - 1 };
+    state.select(Some(
+        state.selected().map_or(0, |index| (index + step) % count),
+    ));
+}
+
+fn render_error(f: &mut Frame, title: &str, message: &str, area: Rect) {
+    let block = Block::default()
+        .title(format!("{title} (any key to close)"))
+        .borders(Borders::ALL)
+        .red();
+    let paragraph = Paragraph::new(message)
+        .block(block)
+        .wrap(Wrap { trim: false });
+    let area = centered_rect(60, 30, area);
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+fn render_status(f: &mut Frame, app: &App, area: Rect) {
+    let status_line = if let Some(edit) = &app.edit_state {
+        match &edit.error {
+            Some(err) => Line::from(vec!["invalid JSON: ".bold(), err.as_str().red()]),
+            None => Line::from(vec![
+                "edit: ".bold(),
+                "Enter to save, Esc to cancel".dark_gray(),
+            ]),
+        }
+    } else if let Some(input) = &app.goto_input {
+        Line::from(vec!["goto: ".bold(), input.as_str().into()])
+    } else if let Some(input) = &app.filter_input {
+        Line::from(vec!["jq | ".bold(), input.as_str().into()])
+    } else if let Some(input) = &app.pipe_input {
+        Line::from(vec!["! ".bold(), input.as_str().into()])
+    } else if let Some(input) = &app.query_input {
+        let mut spans = vec![":".bold(), input.as_str().into()];
+        if let Some(err) = &app.query_error {
+            spans.extend(["  ".into(), err.as_str().red()]);
+        }
+        Line::from(spans)
+    } else if let Some(input) = &app.export_input {
+        Line::from(vec!["export to: ".bold(), input.as_str().into()])
+    } else if let Some(input) = &app.narrow_input {
+        let count = match app.narrow.matches.len() {
+            _ if app.narrow_pending.is_some() || !app.narrow.is_active() => String::new(),
+            0 => "no matches".to_string(),
+            count => format!("{count} matches"),
+        };
+        Line::from(vec![
+            "filter: ".bold(),
+            input.as_str().into(),
+            "  ".into(),
+            count.dark_gray(),
+        ])
+    } else if app.search.editing {
+        let mut spans = Vec::new();
+        if app.search.is_regex() {
+            spans.push("[regex] ".cyan());
+        }
+        if app.search.ignore_case && !app.search.fuzzy {
+            spans.push("[ignore case] ".cyan());
+        }
+        if let Some(subtree) = &app.search.subtree {
+            spans.push(format!("[in {}] ", selected_json_pointer(subtree)).cyan());
+        }
+        match app.search.scope {
+            SearchScope::Both => {}
+            SearchScope::Keys => spans.push("[keys] ".cyan()),
+            SearchScope::Values => spans.push("[values] ".cyan()),
+        }
+        spans.extend([
+            if app.search.fuzzy { "~" } else { "/" }.bold(),
+            app.search.query.as_str().into(),
+            "  ".into(),
+        ]);
+        spans.push(match &app.search.error {
+            Some(err) => err.as_str().red(),
+            None => app.search_status().dark_gray(),
+        });
+        Line::from(spans)
+    } else {
+        let search = if app.search.is_active() {
+            let scope = match &app.search.subtree {
+                Some(subtree) => format!(" in {}", selected_json_pointer(subtree)),
+                None => String::new(),
+            };
+            format!(
+                "search: {}{scope} — {}",
+                app.search.query,
+                app.search_status()
+            )
+        } else {
+            String::new()
+        };
+        Line::from(vec![
+            app.selected_pointer().bold(),
+            "  ".into(),
+            search.yellow(),
+            " ".into(),
+            app.status.as_str().dark_gray(),
+        ])
+    };
+    let selected = app.state.selected();
+    let mut node_info = match compute_node_info(&app.root, &selected) {
+        Some(info) if !selected.is_empty() => format!(" {info}"),
+        _ => String::new(),
+    };
+    if let Some((_, Some((line, column)))) = &app.location {
+        node_info += &format!("  line {line}, col {column}");
+    }
+    let [area, info_area] = Layout::horizontal([
+        Constraint::Min(0),
+        Constraint::Length(node_info.len() as u16),
+    ])
+    .areas(area);
+    f.render_widget(Paragraph::new(status_line), area);
+    f.render_widget(Paragraph::new(node_info.cyan()), info_area);
 }
 
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result<(), Box<dyn Error>> {
-    loop {
+fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    mut tabs: TabManager,
+    keys: &KeyMap,
+) -> Result<Option<ExitOutput>, Box<dyn Error>> {
+    while !tabs.tabs.is_empty() {
+        for app in &mut tabs.tabs {
+            app.check_watched();
+            app.expire_status();
+            app.apply_pending_narrow();
+        }
+        let tab_bar = tabs.tab_bar();
+        let split = tabs.split && tabs.tabs.len() == 2;
+        let active = tabs.active;
         terminal.draw(|f| {
-            let area = f.size();
-
-            let items = Tree::new(app.items.clone())
-                .expect("unique item identifiers")
-                .block(Block::bordered().title(format!(
-                    "{} {:?}",
-                    env!("CARGO_PKG_DESCRIPTION"),
-                    app.state
-                )))
-                .highlight_style(
-                    Style::new()
-                        .fg(Color::Black)
-                        .bg(Color::Gray)
-                        .add_modifier(Modifier::BOLD),
-                );
+            let bar_height = u16::from(tab_bar.is_some());
+            let vertical = Layout::vertical([
+                Constraint::Length(bar_height),
+                Constraint::Min(1),
+                Constraint::Length(1),
+            ]);
+            let [bar, main, status] = vertical.areas(f.size());
+            if let Some(tab_bar) = tab_bar {
+                f.render_widget(tab_bar, bar);
+            }
+            let results_height = if tabs.tabs[active].results_open {
+                RESULTS_HEIGHT
+            } else {
+                0
+            };
+            let [panes, results] =
+                Layout::vertical([Constraint::Min(1), Constraint::Length(results_height)])
+                    .areas(main);
+            if split {
+                let [left, right] =
+                    Layout::horizontal([Constraint::Percentage(50); 2]).areas(panes);
+                for (index, area) in [left, right].into_iter().enumerate() {
+                    let marker = if index == active { " [*]" } else { "" };
+                    render_tree(f, &mut tabs.tabs[index], area, marker);
+                }
+            }
+            let app = &mut tabs.tabs[active];
+            let horizontal = Layout::horizontal([
+                Constraint::Percentage(100 - app.detail_width),
+                Constraint::Percentage(app.detail_width),
+            ]);
+            let [area, detail] = horizontal.areas(panes);
 
             // let vertical =
             //     Layout::vertical([Constraint::Percentage(20), Constraint::Percentage(80)]);
@@ -137,39 +3415,231 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result<(), B
             //     .centered()
             //     .wrap(Wrap { trim: true });
             // f.render_widget(paragraph, instructions);
+            if !split {
+                render_tree(f, app, area, "");
+                if app.detail_width > 0 {
+                    render_detail(f, app, detail);
+                }
+            }
+            if app.results_open {
+                render_results(f, app, results);
+            }
+            render_fullscreen_value(f, app, main);
+            app.update_location();
+            render_status(f, app, status);
             if app.show_cmd_popup {
-                let block = Block::default()
-                    .title("Available commands")
-                    .borders(Borders::ALL);
-                let area = centered_rect(60, 30, area);
-                f.render_widget(Clear, area);
-                f.render_widget(block, area);
-            }
-            f.render_stateful_widget(items, area, &mut app.state);
+                render_commands(f, app, main);
+            }
+            render_tooltip(f, app, main);
+            render_query_results(f, app, main);
+            render_pipe_output(f, app, main);
+            render_finder(f, app, main);
+            render_bookmarks(f, app, main);
+            if let Some((title, message)) = &app.error_popup {
+                render_error(f, title, message, main);
+            }
         })?;
+        let app = tabs.active_mut();
 
         // // main: selected: [ObjectKey("ticket"), ObjectKey("state"), ObjectKey("list"), ArrayIdx(0), ObjectKey("customer_id")]
         // // TODO: https://doc.rust-lang.org/nightly/core/fmt/trait.Debug.html#examples-1
         // println!("selected: {:?}", app.state.selected());
 
         if event::poll(std::time::Duration::from_millis(50))? {
-            match event::read()? {
-                Event::Key(key) => match key.code {
-                    KeyCode::Char('q') => return Ok(()),
-                    KeyCode::Enter | KeyCode::Char(' ') => app.state.toggle_selected(),
-                    KeyCode::Left => app.state.key_left(),
-                    KeyCode::Right => app.state.key_right(),
-                    KeyCode::Down => app.state.key_down(&app.items),
-                    KeyCode::Up => app.state.key_up(&app.items),
-                    KeyCode::Home => app.state.select_first(&app.items),
-                    KeyCode::End => app.state.select_last(&app.items),
-                    KeyCode::PageDown => app.state.scroll_down(3),
-                    KeyCode::PageUp => app.state.scroll_up(3),
-                    // KeyCode::Char('c') => {
-                    //     app.show_cmd_popup = !app.show_cmd_popup;
-                    //     app.show_cmd_popup
-                    // },
-
+            let event = event::read()?;
+            if let Event::Key(_) = event {
+                app.hover = None;
+            }
+            match event {
+                Event::Key(_) if app.error_popup.is_some() => {
+                    app.error_popup = None;
+                    true
+                }
+                Event::Key(key) if app.show_cmd_popup => app.command_input(key.code),
+                Event::Key(key) if app.query_results.is_some() => app.query_results_input(key.code),
+                Event::Key(key) if app.pipe_output.is_some() => app.pipe_output_input(key.code),
+                Event::Key(key) if app.finder_input.is_some() => app.finder_input(key.code),
+                Event::Key(key) if app.bookmarks.is_some() => app.bookmarks_input(key.code),
+                Event::Key(key) if app.view != ViewMode::Tree => app.fullscreen_input(key.code),
+                Event::Mouse(mouse) if app.view != ViewMode::Tree => match mouse.kind {
+                    event::MouseEventKind::ScrollDown => app.fullscreen_input(KeyCode::Down),
+                    event::MouseEventKind::ScrollUp => app.fullscreen_input(KeyCode::Up),
+                    _ => false,
+                },
+                Event::Key(key) if split && Some(key.code) == keys.switch_pane => {
+                    app.tab_command = Some(TabCommand::Next);
+                    true
+                }
+                Event::Key(key)
+                    if app.search.editing && key.modifiers.contains(KeyModifiers::CONTROL) =>
+                {
+                    match key.code {
+                        KeyCode::Char('f') => app.toggle_fuzzy(),
+                        KeyCode::Char('r') => app.toggle_regex(),
+                        KeyCode::Char('t') => app.toggle_ignore_case(),
+                        KeyCode::Char('k') => app.toggle_search_scope(SearchScope::Keys),
+                        KeyCode::Char('v') => app.toggle_search_scope(SearchScope::Values),
+                        _ => false,
+                    }
+                }
+                Event::Key(key) if app.search.editing => app.search_input(key.code),
+                Event::Key(key) if app.narrow_input.is_some() => app.narrow_input(key.code),
+                Event::Key(key) if app.edit_state.is_some() => app.edit_input(key),
+                Event::Key(key) if app.goto_input.is_some() => app.goto_input(key.code),
+                Event::Key(key) if app.query_input.is_some() => app.query_input(key.code),
+                Event::Key(key) if app.filter_input.is_some() => app.filter_input(key.code),
+                Event::Key(key) if app.pipe_input.is_some() => app.pipe_input(key.code),
+                Event::Key(key) if app.export_input.is_some() => app.export_input(key.code),
+                Event::Key(key) if app.focus == Pane::Results => app.results_input(key.code, keys),
+                Event::Key(key) if app.focus == Pane::Detail => match Some(key.code) {
+                    code if code == keys.quit => {
+                        app.quit = true;
+                        true
+                    }
+                    code if code == keys.switch_pane => app.toggle_focus(),
+                    Some(KeyCode::Esc) => {
+                        app.focus = Pane::Tree;
+                        true
+                    }
+                    code if code == keys.down => app.scroll_detail(1),
+                    code if code == keys.up => app.scroll_detail(-1),
+                    code if code == keys.page_down => app.scroll_detail(10),
+                    code if code == keys.page_up => app.scroll_detail(-10),
+                    code if code == keys.first => {
+                        app.detail_scroll = 0;
+                        true
+                    }
+                    _ => true,
+                },
+                Event::Key(key)
+                    if key.modifiers.contains(KeyModifiers::SHIFT)
+                        && matches!(key.code, KeyCode::Left | KeyCode::Right) =>
+                {
+                    app.scroll_tree(key.code == KeyCode::Right)
+                }
+                Event::Key(key) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    match key.code {
+                        // Ctrl+Tab reaches only terminals reporting it apart from Tab
+                        KeyCode::Right | KeyCode::Tab => app.tab_command = Some(TabCommand::Next),
+                        KeyCode::Left | KeyCode::BackTab => {
+                            app.tab_command = Some(TabCommand::Prev)
+                        }
+                        KeyCode::Char('f') => {
+                            app.toggle_fuzzy();
+                        }
+                        KeyCode::Char('r') => {
+                            app.toggle_regex();
+                        }
+                        KeyCode::Char('t') => {
+                            app.toggle_ignore_case();
+                        }
+                        KeyCode::Char('k') => {
+                            app.toggle_search_scope(SearchScope::Keys);
+                        }
+                        KeyCode::Char('v') => {
+                            app.toggle_search_scope(SearchScope::Values);
+                        }
+                        KeyCode::Char('z') => {
+                            app.undo_edit();
+                        }
+                        KeyCode::Char('p') => {
+                            app.open_finder();
+                        }
+                        KeyCode::Char('y') => {
+                            app.redo_edit();
+                        }
+                        _ => {}
+                    }
+                    true
+                }
+                Event::Key(key) => match Some(key.code) {
+                    code if code == keys.quit => {
+                        app.quit = true;
+                        true
+                    }
+                    code if code == keys.search => app.start_search(),
+                    code if code == keys.search_subtree => app.start_subtree_search(),
+                    code if code == keys.narrow => app.start_narrow(),
+                    code if code == keys.goto => {
+                        app.goto_input = Some(String::new());
+                        true
+                    }
+                    code if code == keys.filter => {
+                        app.filter_input = Some(String::new());
+                        true
+                    }
+                    code if code == keys.pipe => {
+                        app.pipe_input = Some(String::new());
+                        true
+                    }
+                    code if code == keys.query => {
+                        app.query_input = Some(String::new());
+                        true
+                    }
+                    code if code == keys.next_match => app.next_match(true),
+                    code if code == keys.prev_match => app.next_match(false),
+                    code if code == keys.results => app.toggle_results(),
+                    code if code == keys.next_same_key => app.same_key(true),
+                    code if code == keys.prev_same_key => app.same_key(false),
+                    code if code == keys.clear_search && app.narrow.is_active() => {
+                        app.clear_narrow()
+                    }
+                    code if code == keys.clear_search => app.clear_search(),
+                    code if code == keys.command_palette => {
+                        app.show_cmd_popup = true;
+                        true
+                    }
+                    code if code == keys.copy => app.copy_selected(),
+                    code if code == keys.copy_pointer => app.copy_pointer(),
+                    code if code == keys.bookmark => app.add_bookmark(),
+                    code if code == keys.bookmarks => app.show_bookmarks(),
+                    code if code == keys.toggle_bookmark => app.toggle_bookmark(),
+                    code if code == keys.next_bookmark => app.cycle_bookmark(true),
+                    code if code == keys.prev_bookmark => app.cycle_bookmark(false),
+                    code if code == keys.nested => app.toggle_nested(),
+                    code if code == keys.base64 => app.toggle_base64(),
+                    code if code == keys.url_decode => app.toggle_url_decoded(),
+                    code if code == keys.edit => app.start_edit(),
+                    code if code == keys.reload => app.reload_input(),
+                    code if code == keys.print => app.toggle_print(),
+                    code if code == keys.paste => app.paste_clipboard(),
+                    code if code == keys.export => {
+                        app.export_input = Some(String::new());
+                        true
+                    }
+                    code if code == keys.write_tree => app.write_tree_text(),
+                    code if code == keys.fullscreen => app.show_fullscreen(),
+                    code if code == keys.expand_all => app.expand_all(),
+                    code if code == keys.sort_keys => app.toggle_sort(false),
+                    code if code == keys.sort_types => app.toggle_sort(true),
+                    code if code == keys.timestamps => app.toggle_timestamps(),
+                    code if code == keys.number_format => app.cycle_number_format(),
+                    code if code == keys.collapse_all => app.collapse_all(),
+                    code if code == keys.switch_pane => app.toggle_focus(),
+                    code if code == keys.close_tab => {
+                        app.tab_command = Some(TabCommand::Close);
+                        true
+                    }
+                    code if code == keys.toggle && app.select.is_some() => {
+                        app.pick_path(app.select.unwrap_or(PathFormat::Pointer))
+                    }
+                    code if code == keys.toggle || code == keys.toggle_alt => {
+                        app.state.toggle_selected()
+                    }
+                    code if code == keys.scroll_left => app.scroll_tree(false),
+                    code if code == keys.scroll_right => app.scroll_tree(true),
+                    code if code == keys.left => app.state.key_left(),
+                    code if code == keys.right => app.state.key_right(),
+                    code if code == keys.down => app.state.key_down(&app.items),
+                    code if code == keys.up => app.state.key_up(&app.items),
+                    code if code == keys.first => app.state.select_first(&app.items),
+                    code if code == keys.last => app.state.select_last(&app.items),
+                    code if code == keys.page_down => app.state.scroll_down(3),
+                    code if code == keys.page_up => app.state.scroll_up(3),
+                    Some(KeyCode::Char(c @ '1'..='9')) => {
+                        app.tab_command = Some(TabCommand::Select(c as usize - '1' as usize));
+                        true
+                    }
                     // KeyCode::F(1) => {
                     //     let t = Content {
                     //         key: app.state.selected(),
@@ -183,12 +3653,27 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result<(), B
                 Event::Mouse(mouse) => match mouse.kind {
                     event::MouseEventKind::ScrollDown => app.state.scroll_down(1),
                     event::MouseEventKind::ScrollUp => app.state.scroll_up(1),
+                    event::MouseEventKind::Down(event::MouseButton::Left) => {
+                        app.hover = None;
+                        app.click(mouse.column, mouse.row)
+                    }
+                    event::MouseEventKind::Moved => app.hover_at(mouse.column, mouse.row),
                     _ => true,
                 },
                 _ => true,
             };
+            if app.quit {
+                let output = app.exit_output();
+                tabs.save_sessions();
+                return Ok(output);
+            }
+            app.sync_lazy_items();
+            if let Some(command) = app.tab_command.take() {
+                tabs.run(command);
+            }
         }
     }
+    Ok(None)
 }
 
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
@@ -207,26 +3692,425 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     .split(popup_layout[1])[1]
 }
 
+#[derive(Default)]
+struct Args {
+    paths: Vec<String>,
+    stdin: bool,
+    headers: Vec<String>,
+    json: Option<String>,
+    detail_width: Option<u16>,
+    page_size: Option<usize>,
+    input: InputOptions,
+    watch: bool,
+    lazy: bool,
+    dump_config: bool,
+    no_session: bool,
+    diff: bool,
+    print: Option<ExitPrint>,
+    clipboard: bool,
+    schema: Option<PathBuf>,
+    /// Only validate the input against the schema
+    validate: bool,
+    theme: Option<String>,
+    ignore_case: bool,
+    filter: Option<String>,
+    select: Option<PathFormat>,
+    /// Characters of the previews of collapsed nodes
+    compact: Option<usize>,
+    /// Levels of the tree opened at start, instead of the saved session's
+    max_depth: Option<usize>,
+}
+
+impl Args {
+    fn input_sources(&self) -> Vec<InputSource> {
+        if let Some(json) = &self.json {
+            return vec![InputSource::Inline(json.clone())];
+        }
+        if self.clipboard {
+            return vec![InputSource::Clipboard];
+        }
+        if self.stdin || self.paths.is_empty() {
+            return vec![InputSource::Stdin];
+        }
+        self.paths
+            .iter()
+            .map(|path| match path {
+                stdin if stdin == "-" => InputSource::Stdin,
+                url if input::is_url(url) => InputSource::Url {
+                    url: url.clone(),
+                    headers: self.headers.clone(),
+                },
+                path => InputSource::File(PathBuf::from(path)),
+            })
+            .collect()
+    }
+}
+
+const USAGE: &str = "\
+Usage: json_viewer [OPTIONS] [FILE | URL | -]...
+       command | json_viewer [OPTIONS]
+       json_viewer [OPTIONS] --json TEXT
+       json_viewer [OPTIONS] --clipboard
+       json_viewer --validate SCHEMA [FILE | URL | -]...
+       json_viewer --select [--select-format pointer|jq|jsonpath] [FILE | URL | -]
+
+Nothing to show: give a file or URL, or pipe JSON in (`--` reads stdin anyway).
+";
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args::default();
+    args.input.max_size = Some(DEFAULT_MAX_SIZE);
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-H" | "--header" => match iter.next() {
+                Some(header) => args.headers.push(header),
+                None => return Err(format!("{arg} requires a value")),
+            },
+            "--json" => match iter.next() {
+                Some(json) => args.json = Some(json),
+                None => return Err(format!("{arg} requires a value")),
+            },
+            "--detail-width" => match iter.next().and_then(|width| width.parse().ok()) {
+                Some(width @ 0..=90) => args.detail_width = Some(width),
+                _ => return Err(format!("{arg} requires a percentage from 0 to 90")),
+            },
+            "--page-size" => match iter.next().and_then(|size| size.parse().ok()) {
+                Some(size) => args.page_size = Some(size),
+                None => {
+                    return Err(format!(
+                        "{arg} requires a number of elements (0 for no pages)"
+                    ))
+                }
+            },
+            "--ndjson" => args.input.format = Some(InputFormat::NdJson),
+            "--jsonc" => args.input.format = Some(InputFormat::Jsonc),
+            "--json5" => args.input.format = Some(InputFormat::Json5),
+            "--yaml" => args.input.format = Some(InputFormat::Yaml),
+            "--toml" => args.input.format = Some(InputFormat::Toml),
+            "--csv" => args.input.format = Some(InputFormat::Csv),
+            "--delimiter" => match iter.next().as_deref().map(str::as_bytes) {
+                Some(b"\\t" | b"tab") => args.input.delimiter = Some(b'\t'),
+                Some(&[delimiter]) => args.input.delimiter = Some(delimiter),
+                _ => return Err(format!("{arg} requires a single character")),
+            },
+            "--max-size" => match iter.next().as_deref().and_then(parse_size) {
+                Some(0) => args.input.max_size = None,
+                Some(size) => args.input.max_size = Some(size),
+                None => return Err(format!("{arg} requires a size, e.g. 500M or 2G")),
+            },
+            "--csv-numbers" => args.input.csv_numbers = true,
+            "--msgpack" => args.input.format = Some(InputFormat::MsgPack),
+            "--cbor" => args.input.format = Some(InputFormat::Cbor),
+            "--bson" => args.input.format = Some(InputFormat::Bson),
+            "--har" => args.input.format = Some(InputFormat::Har),
+            "--extended-json" => args.input.extended_json = true,
+            "--bytes-base64" => args.input.bytes_base64 = true,
+            "--strict" => args.input.strict = true,
+            "--parse-nested" => args.input.parse_nested = true,
+            "--lenient" => args.input.lenient = true,
+            "--gzip" => args.input.gzip = true,
+            "--watch" => args.watch = true,
+            "--lazy" => args.lazy = true,
+            "--dump-config" => args.dump_config = true,
+            "--no-session" => args.no_session = true,
+            "--diff" => args.diff = true,
+            "--print-selected" => args.print = Some(ExitPrint::Selected),
+            "--print-all" => args.print = Some(ExitPrint::All),
+            "--clipboard" => args.clipboard = true,
+            "--ignore-case" => args.ignore_case = true,
+            "--select" => args.select = args.select.or(Some(PathFormat::Pointer)),
+            "--select-format" => match iter.next().map(|format| format.parse()) {
+                Some(Ok(format)) => args.select = Some(format),
+                Some(Err(err)) => return Err(err),
+                None => return Err(format!("{arg} requires pointer, jq or jsonpath")),
+            },
+            format if format.starts_with("--select-format=") => {
+                args.select = Some(format["--select-format=".len()..].parse()?);
+            }
+            "--max-depth" => match iter.next().and_then(|depth| depth.parse().ok()) {
+                Some(depth) => args.max_depth = Some(depth),
+                None => return Err(format!("{arg} requires a number of levels")),
+            },
+            "--compact" => args.compact = Some(DEFAULT_PREVIEW_CHARS),
+            compact if compact.starts_with("--compact=") => {
+                match compact["--compact=".len()..].parse() {
+                    Ok(chars) => args.compact = Some(chars),
+                    Err(_) => return Err("--compact= requires a number of characters".to_string()),
+                }
+            }
+            "--filter" => match iter.next() {
+                Some(program) => args.filter = Some(program),
+                None => return Err(format!("{arg} requires a jq program")),
+            },
+            "--theme" => match iter.next() {
+                Some(theme) => args.theme = Some(theme),
+                None => return Err(format!("{arg} requires a theme name or file")),
+            },
+            "--schema" | "--validate" => match iter.next() {
+                Some(schema) => {
+                    args.schema = Some(PathBuf::from(schema));
+                    args.validate |= arg == "--validate";
+                }
+                None => return Err(format!("{arg} requires a schema file")),
+            },
+            "--" => args.stdin = true,
+            _ => args.paths.push(arg),
+        }
+    }
+    if args.paths.iter().filter(|path| *path == "-").count() > 1 {
+        return Err("- (stdin) can be given only once".to_string());
+    }
+    if args.diff && args.paths.len() != 2 {
+        return Err("--diff requires exactly two files".to_string());
+    }
+    if args.json.is_some() && (args.stdin || !args.paths.is_empty()) {
+        return Err("--json can't be combined with file paths or stdin".to_string());
+    }
+    if args.clipboard && (args.json.is_some() || args.stdin || !args.paths.is_empty()) {
+        return Err("--clipboard can't be combined with --json, file paths or stdin".to_string());
+    }
+    if args.select.is_some() && args.print.is_some() {
+        return Err("--select can't be combined with --print-selected or --print-all".to_string());
+    }
+    Ok(args)
+}
+
+/// Top level of a big (or `--lazy`) JSON file, the rest is read as it is expanded. `None`
+/// for other input, when a filter, schema or diff needs the whole document, or when the
+/// file is not a single object or array of plain JSON (its parser then recovers or reports).
+fn open_lazily(source: &InputSource, args: &Args) -> Option<(LazyDocument, Value)> {
+    let InputSource::File(path) = source else {
+        return None;
+    };
+    let json = matches!(
+        args.input.format.or_else(|| source.format()),
+        None | Some(InputFormat::Json)
+    );
+    let whole = args.filter.is_some()
+        || args.schema.is_some()
+        || args.diff
+        || args.input.parse_nested
+        || args.input.gzip;
+    if !json || whole || !(args.lazy || fs::metadata(path).ok()?.len() > LAZY_FILE_SIZE) {
+        return None;
+    }
+    LazyDocument::open(path).ok().flatten()
+}
+
+/// Reads and compiles JSON Schema file
+fn load_schema(path: &Path) -> Result<Schema, String> {
+    let source = InputSource::File(path.to_path_buf());
+    let document = load_with_progress(&source, &InputOptions::default())?;
+    Schema::compile(&document.value).map_err(|err| err.to_string())
+}
+
+/// Loads the input on a worker thread, showing progress on the terminal meanwhile.
+/// Runs before raw mode is enabled, so Ctrl-C simply terminates.
+fn load_with_progress(source: &InputSource, options: &InputOptions) -> Result<Document, String> {
+    let (sender, progress) = mpsc::channel();
+    let worker = thread::spawn({
+        let source = source.clone();
+        let options = options.clone();
+        move || {
+            let report = |stage: Progress| {
+                let _ = sender.send(stage);
+            };
+            let allow_larger = |max_size| confirm_larger(&source, max_size);
+            load_input(&source, &options, &report, &allow_larger).map_err(|err| err.to_string())
+        }
+    });
+    let show = io::stderr().is_terminal();
+    let mut shown = false;
+    // ends once the worker is done and the sender is dropped
+    for stage in progress {
+        if show {
+            eprint!("\r{source}: {stage}\x1b[K");
+            shown = true;
+        }
+    }
+    if shown {
+        eprint!("\r\x1b[K");
+    }
+    worker
+        .join()
+        .unwrap_or_else(|_| Err("loading panicked".to_string()))
+}
+
+/// Bytes, or kilo-, mega- or gigabytes with `K`, `M` or `G` suffix
+fn parse_size(size: &str) -> Option<u64> {
+    let (number, shift) = match size.char_indices().last()? {
+        (index, 'K' | 'k') => (&size[..index], 10),
+        (index, 'M' | 'm') => (&size[..index], 20),
+        (index, 'G' | 'g') => (&size[..index], 30),
+        _ => (size, 0),
+    };
+    number.parse::<u64>().ok()?.checked_mul(1 << shift)
+}
+
+/// Asks on the terminal whether to load input over the size limit, refuses without one
+fn confirm_larger(source: &InputSource, max_size: u64) -> bool {
+    if !io::stderr().is_terminal() {
+        return false;
+    }
+    let Ok(tty) = fs::File::open("/dev/tty") else {
+        return false;
+    };
+    eprint!(
+        "\r{source}: larger than {}, load anyway? [y/N] \x1b[K",
+        format_size(max_size)
+    );
+    let mut answer = String::new();
+    let _ = io::BufReader::new(tty).read_line(&mut answer);
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     // let _log2 = log2::open(&format!("{}.log", env!("CARGO_PKG_NAME"))).start();
 
-    let mut stdin = io::stdin();
-    let mut buff = String::new();
-    stdin.read_to_string(&mut buff)?;
-    let json_input: Value = serde_json::from_str(&buff)?;
-    // println!("json_input: {json_input:?}");
+    let args = parse_args().unwrap_or_else(|err| {
+        eprintln!("{err}");
+        exit(1);
+    });
+    if args.dump_config {
+        print!("{}", Config::default().to_toml());
+        return Ok(());
+    }
+    let config = load_config().unwrap_or_else(|err| {
+        eprintln!("{err}");
+        exit(1);
+    });
+    let theme = args.theme.as_deref().map(|theme| {
+        load_theme(theme).unwrap_or_else(|err| {
+            eprintln!("{err}");
+            exit(1);
+        })
+    });
+    let sources = args.input_sources();
+    // nothing piped in, reading would wait for typing (unless asked for with `--`)
+    if !args.stdin
+        && sources
+            .iter()
+            .any(|source| matches!(source, InputSource::Stdin))
+        && io::stdin().is_terminal()
+    {
+        if args.paths.is_empty() {
+            eprint!("{USAGE}");
+        } else {
+            eprintln!("-: stdin is a terminal, pipe a document in to read it");
+        }
+        exit(1);
+    }
+    let schema = args.schema.as_deref().map(|path| {
+        load_schema(path).unwrap_or_else(|err| {
+            eprintln!("{}: {err}", path.display());
+            exit(1);
+        })
+    });
+    if let (true, Some(schema)) = (args.validate, &schema) {
+        let mut valid = true;
+        for source in sources {
+            let document = load_with_progress(&source, &args.input).unwrap_or_else(|err| {
+                eprintln!("{source}: {err}");
+                exit(1);
+            });
+            for violation in schema.validate(&document.value) {
+                eprintln!("{source}: {violation}");
+                valid = false;
+            }
+        }
+        exit(if valid { 0 } else { 1 });
+    }
+    let schema = schema.map(Rc::new);
+    let mut apps = Vec::new();
+    for source in sources {
+        let (document, lazy_document) = match open_lazily(&source, &args) {
+            Some((lazy_document, root)) => (Document::from(root), Some(lazy_document)),
+            None => {
+                let document = load_with_progress(&source, &args.input).unwrap_or_else(|err| {
+                    eprintln!("{source}: {err}");
+                    exit(1);
+                });
+                (document, None)
+            }
+        };
+        // println!("json_input: {json_input:?}");
 
-    let items = root_tree_items(&json_input);
-    // println!("items: {items:?}");
+        let mut app = App::new(document.value, source, args.input.clone());
+        if lazy_document.is_some() {
+            app.lazy = true;
+            app.lazy_document = lazy_document;
+            app.rebuild_items();
+        }
+        app.source_text = document.source;
+        if let Some(program) = &args.filter {
+            app.apply_filter(program).unwrap_or_else(|err| {
+                eprintln!("{}: {err}", app.source);
+                exit(1);
+            });
+            app.status.clear();
+        }
+        if let Some(notice) = document.notice {
+            app.status = notice;
+        }
+        if let Some(schema) = &schema {
+            app.schema = Some(Rc::clone(schema));
+            app.check_schema();
+            let count: usize = app.violations.values().map(Vec::len).sum();
+            if count > 0 {
+                app.status = format!("{count} schema violations");
+            }
+            app.rebuild_items();
+        }
+        // println!("items: {:?}", app.items);
+        app.detail_width = args.detail_width.unwrap_or(DEFAULT_DETAIL_WIDTH);
+        if let Some(page_size) = args.page_size {
+            app.page_size = page_size;
+            app.rebuild_items();
+        }
+        if let Some(chars) = args.compact {
+            app.preview_chars = chars;
+            app.rebuild_items();
+        }
+        app.print_on_exit = args.print;
+        app.select = args.select;
+        app.search.ignore_case = args.ignore_case || config.search.ignore_case;
+        if let Some(theme) = &theme {
+            app.colors = theme.colors;
+            app.rebuild_items();
+        }
+        if args.watch {
+            app.watch();
+        }
+        if args.lazy && !app.lazy {
+            app.lazy = true;
+            app.rebuild_items();
+        }
+        if !args.no_session {
+            app.restore_session();
+        }
+        if let Some(depth) = args.max_depth {
+            app.open_to_max_depth(depth);
+        }
+        apps.push(app);
+    }
+    let mut tabs = TabManager::new(apps);
+    if args.diff {
+        tabs.diff();
+    }
 
     enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
+    // stdout may be piped to take the printed document
+    let mut screen: Box<dyn Write> = if io::stdout().is_terminal() {
+        Box::new(io::stdout())
+    } else {
+        Box::new(io::stderr())
+    };
+    execute!(screen, EnterAlternateScreen, EnableMouseCapture)?;
+    let backend = CrosstermBackend::new(screen);
     let mut terminal = Terminal::new(backend)?;
 
-    let app = App::new(items);
-    let res = run_app(&mut terminal, app);
+    let res = run_app(&mut terminal, tabs, &config.keys);
 
     disable_raw_mode()?;
     execute!(
@@ -236,9 +4120,20 @@ fn main() -> Result<(), Box<dyn Error>> {
     )?;
     terminal.show_cursor()?;
 
-    if let Err(err) = res {
-        println!("{err:?}");
-        exit(1);
+    match res {
+        Ok(Some(ExitOutput::Json(output))) => {
+            let mut stdout = io::stdout().lock();
+            serde_json::to_writer_pretty(&mut stdout, &output)?;
+            writeln!(stdout)?;
+        }
+        Ok(Some(ExitOutput::Text(output))) => println!("{output}"),
+        // nothing picked, scripts can tell it from an empty pointer of the root
+        Ok(None) if args.select.is_some() => exit(1),
+        Ok(None) => {}
+        Err(err) => {
+            println!("{err:?}");
+            exit(1);
+        }
     }
     Ok(())
 }
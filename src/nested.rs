@@ -0,0 +1,281 @@
+use base64::{
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine,
+};
+use percent_encoding::percent_decode_str;
+use serde_json::Value;
+use std::{collections::HashMap, fmt::Write};
+
+use crate::JsonPointer;
+
+/// Levels of JSON embedded in strings expanded by `expand_nested`
+pub const MAX_NESTED_DEPTH: usize = 8;
+/// Bytes shown in hex dump of binary data
+const HEX_DUMP_BYTES: usize = 256;
+/// Unpadded strings shorter than this decoding to binary are words (`true`, `name`), not base64
+const MIN_UNPADDED_BINARY_LEN: usize = 20;
+
+/// How an expanded string held its JSON
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Embedding {
+    Json,
+    Base64,
+    Url,
+}
+
+impl Embedding {
+    /// Shown after the key of the expanded node
+    pub fn marker(self) -> &'static str {
+        match self {
+            Self::Json => "⤷json",
+            Self::Base64 => "⤷base64",
+            Self::Url => "⤷url",
+        }
+    }
+}
+
+/// Original strings of the expanded nodes, by path
+pub type RawStrings = HashMap<Vec<JsonPointer>, (Embedding, String)>;
+
+/// Object or array encoded in a string
+pub fn parse_embedded(text: &str) -> Option<Value> {
+    match serde_json::from_str(text) {
+        Ok(value @ (Value::Object(_) | Value::Array(_))) => Some(value),
+        _ => None,
+    }
+}
+
+/// Replaces the string at `path` by the JSON it holds, keeping the original in `raw`
+pub fn expand_at(value: &mut Value, path: &[JsonPointer], raw: &mut RawStrings) -> bool {
+    let Some(parsed) = value.as_str().and_then(parse_embedded) else {
+        return false;
+    };
+    replace_embedded(value, parsed, path, Embedding::Json, raw);
+    true
+}
+
+/// Replaces the string at `path` by its decoded value, keeping the original in `raw`
+pub fn replace_embedded(
+    value: &mut Value,
+    decoded: Value,
+    path: &[JsonPointer],
+    embedding: Embedding,
+    raw: &mut RawStrings,
+) {
+    if let Value::String(text) = std::mem::replace(value, decoded) {
+        raw.insert(path.to_vec(), (embedding, text));
+    }
+}
+
+/// Puts back the original string at `path`, forgetting expansions under it
+pub fn collapse_at(value: &mut Value, path: &[JsonPointer], raw: &mut RawStrings) -> bool {
+    let Some((_, text)) = raw.remove(path) else {
+        return false;
+    };
+    raw.retain(|nested, _| !nested.starts_with(path));
+    *value = Value::String(text);
+    true
+}
+
+/// Decoded view of a base64 string
+#[derive(Clone, PartialEq, Debug)]
+pub enum DecodedValue {
+    Utf8(String),
+    /// Length and hex dump of binary data
+    Hex(String),
+}
+
+impl DecodedValue {
+    /// Single line shown in the tree instead of the encoded string
+    pub fn summary(&self) -> String {
+        match self {
+            Self::Utf8(text) => Value::String(text.clone()).to_string(),
+            Self::Hex(dump) => format!("<{}>", dump.lines().next().unwrap_or_default()),
+        }
+    }
+
+    /// Text shown in the value pane
+    pub fn text(&self) -> &str {
+        match self {
+            Self::Utf8(text) | Self::Hex(text) => text,
+        }
+    }
+}
+
+/// Decodes standard or URL-safe base64, padded or not. `None` for strings with
+/// characters outside of the alphabets or of impossible length, and for short unpadded ones
+/// not decoding to printable text, which any 4n letters do.
+pub fn try_decode_base64(s: &str) -> Option<DecodedValue> {
+    const CONFIG: GeneralPurposeConfig =
+        GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+    const STANDARD: GeneralPurpose = GeneralPurpose::new(&alphabet::STANDARD, CONFIG);
+    const URL_SAFE: GeneralPurpose = GeneralPurpose::new(&alphabet::URL_SAFE, CONFIG);
+    let s = s.trim();
+    if s.len() < 4 {
+        return None;
+    }
+    let bytes = STANDARD.decode(s).or_else(|_| URL_SAFE.decode(s)).ok()?;
+    let printable = |text: &str| {
+        text.chars()
+            .all(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t'))
+    };
+    match String::from_utf8(bytes) {
+        Ok(text) if printable(&text) => Some(DecodedValue::Utf8(text)),
+        _ if !s.ends_with('=') && s.len() < MIN_UNPADDED_BINARY_LEN => None,
+        Ok(text) => Some(DecodedValue::Hex(hex_dump(text.as_bytes()))),
+        Err(err) => Some(DecodedValue::Hex(hex_dump(err.as_bytes()))),
+    }
+}
+
+/// Decodes `%XX` escapes of a string having at least one, `None` for other strings
+/// or when the decoded bytes aren't UTF-8
+pub fn url_decode(s: &str) -> Option<String> {
+    let escaped = s.as_bytes().windows(3).any(|window| {
+        window[0] == b'%' && window[1].is_ascii_hexdigit() && window[2].is_ascii_hexdigit()
+    });
+    if !escaped {
+        return None;
+    }
+    percent_decode_str(s)
+        .decode_utf8()
+        .ok()
+        .map(|decoded| decoded.into_owned())
+}
+
+/// Length, then the first bytes 16 per line
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut dump = format!("{} bytes\n", bytes.len());
+    for (index, line) in bytes.chunks(16).take(HEX_DUMP_BYTES / 16).enumerate() {
+        let _ = write!(dump, "{:08x} ", index * 16);
+        for byte in line {
+            let _ = write!(dump, " {byte:02x}");
+        }
+        dump.push('\n');
+    }
+    if bytes.len() > HEX_DUMP_BYTES {
+        dump.push_str("…\n");
+    }
+    dump
+}
+
+/// Expands strings holding JSON objects or arrays throughout the document, including
+/// the ones found inside expanded strings, up to `MAX_NESTED_DEPTH` levels
+pub fn expand_nested(value: &mut Value, path: &mut Vec<JsonPointer>, raw: &mut RawStrings) {
+    expand_nested_to(value, path, raw, 0);
+}
+
+fn expand_nested_to(
+    value: &mut Value,
+    path: &mut Vec<JsonPointer>,
+    raw: &mut RawStrings,
+    depth: usize,
+) {
+    if value.is_string() && depth < MAX_NESTED_DEPTH && expand_at(value, path, raw) {
+        expand_nested_to(value, path, raw, depth + 1);
+        return;
+    }
+    match value {
+        Value::Object(object) => {
+            for (key, child) in object.iter_mut() {
+                path.push(JsonPointer::ObjectKey(key.clone()));
+                expand_nested_to(child, path, raw, depth);
+                path.pop();
+            }
+        }
+        Value::Array(array) => {
+            for (index, child) in array.iter_mut().enumerate() {
+                path.push(JsonPointer::ArrayIdx(index));
+                expand_nested_to(child, path, raw, depth);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn expands_nested_strings_up_to_limit() {
+        let mut value = json!({"payload": "{\"id\": 1, \"inner\": \"[1, 2]\"}", "text": "1"});
+        let mut raw = RawStrings::new();
+        expand_nested(&mut value, &mut Vec::new(), &mut raw);
+        assert_eq!(
+            value,
+            json!({"payload": {"id": 1, "inner": [1, 2]}, "text": "1"})
+        );
+        assert_eq!(raw.len(), 2);
+
+        let payload = vec![JsonPointer::ObjectKey("payload".to_string())];
+        assert!(collapse_at(&mut value["payload"], &payload, &mut raw));
+        assert_eq!(
+            value["payload"],
+            json!("{\"id\": 1, \"inner\": \"[1, 2]\"}")
+        );
+        assert!(raw.is_empty());
+
+        let mut value = json!("[]");
+        for _ in 0..MAX_NESTED_DEPTH + 2 {
+            value = Value::String(format!("[{value}]"));
+        }
+        expand_nested(&mut value, &mut Vec::new(), &mut raw);
+        assert_eq!(raw.len(), MAX_NESTED_DEPTH);
+    }
+
+    #[test]
+    fn decodes_base64_kinds() {
+        assert_eq!(
+            try_decode_base64("eyJpZCI6IDF9"),
+            Some(DecodedValue::Utf8(r#"{"id": 1}"#.to_string()))
+        );
+        assert_eq!(
+            try_decode_base64("aGVsbG8gd29ybGQ"),
+            Some(DecodedValue::Utf8("hello world".to_string()))
+        );
+        let binary = try_decode_base64("3q2-7w==").unwrap();
+        assert_eq!(
+            binary,
+            DecodedValue::Hex("4 bytes\n00000000  de ad be ef\n".to_string())
+        );
+        assert_eq!(binary.summary(), "<4 bytes>");
+        for text in ["hello world", "abc", "abcde", "ab/c-d", "user@example.com"] {
+            assert!(try_decode_base64(text).is_none(), "{text}");
+        }
+        // words of 4n letters decode, to bytes that aren't text
+        for text in [
+            "true",
+            "name",
+            "test",
+            "abcd",
+            "false123",
+            "backgroundColor1",
+        ] {
+            assert!(try_decode_base64(text).is_none(), "{text}");
+        }
+        assert_eq!(
+            try_decode_base64("Zm9v"),
+            Some(DecodedValue::Utf8("foo".to_string()))
+        );
+        let long = try_decode_base64("3q2-7wAAAAAAAAAAAAAAAAAA").unwrap();
+        assert_eq!(long.summary(), "<18 bytes>");
+    }
+
+    #[test]
+    fn decodes_url_escapes() {
+        assert_eq!(
+            url_decode("q%3Dcaf%C3%A9%20au%20lait%26x").as_deref(),
+            Some("q=café au lait&x")
+        );
+        assert_eq!(
+            url_decode("%7B%22a%22%3A1%7D").as_deref(),
+            Some(r#"{"a":1}"#)
+        );
+        for text in ["100%", "50% off", "a+b", "%zz", "%FF%FE"] {
+            assert!(url_decode(text).is_none(), "{text}");
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use serde_json::Value;
+use std::{iter::Peekable, str::Chars, str::FromStr};
+
+use crate::JsonPointer;
+
+/// Syntax a path is written in for other tools
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PathFormat {
+    /// RFC 6901 JSON Pointer, `/data/items/0/price`
+    Pointer,
+    /// jq filter, `.data.items[0].price`
+    Jq,
+    /// JSONPath, `$.data.items[0].price`
+    JsonPath,
+}
+
+impl FromStr for PathFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pointer" => Ok(Self::Pointer),
+            "jq" => Ok(Self::Jq),
+            "jsonpath" => Ok(Self::JsonPath),
+            _ => Err(format!(
+                "unknown path format '{s}', expected pointer, jq or jsonpath"
+            )),
+        }
+    }
+}
+
+/// Builds RFC 6901 JSON Pointer, e.g. `/ticket/state/list/0/customer_id`
+pub fn selected_json_pointer(selected: &[JsonPointer]) -> String {
+    selected
+        .iter()
+        .filter(|pointer| !matches!(pointer, JsonPointer::ArrayPage(..) | JsonPointer::None))
+        .map(|pointer| {
+            format!(
+                "/{}",
+                pointer.to_string().replace('~', "~0").replace('/', "~1")
+            )
+        })
+        .collect()
+}
+
+/// Writes the document path of a node in `format`, keys that aren't identifiers are quoted
+pub fn format_path(path: &[JsonPointer], format: PathFormat) -> String {
+    if format == PathFormat::Pointer {
+        return selected_json_pointer(path);
+    }
+    let mut segments = String::new();
+    for pointer in path {
+        match pointer {
+            JsonPointer::ObjectKey(key) if is_identifier(key) => {
+                segments.push('.');
+                segments.push_str(key);
+            }
+            JsonPointer::ObjectKey(key) => segments += &format!("[{}]", Value::from(key.as_str())),
+            JsonPointer::ArrayIdx(index) => segments += &format!("[{index}]"),
+            JsonPointer::ArrayPage(..) | JsonPointer::None => {}
+        }
+    }
+    match format {
+        PathFormat::Jq if !segments.starts_with('.') => format!(".{segments}"),
+        PathFormat::JsonPath => format!("${segments}"),
+        _ => segments,
+    }
+}
+
+/// Key that can follow `.` as is in jq and JSONPath
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Path resolved only partly
+pub struct Unresolved {
+    /// Deepest node that exists
+    pub resolved: Vec<JsonPointer>,
+    pub message: String,
+}
+
+/// Parses RFC 6901 JSON Pointer (`/data/items/0/price`) or dotted path (`data.items[0].price`,
+/// also `$.data["a.b"]`). Pointer segments are all keys, they are taken as indices by arrays.
+pub fn parse_path(text: &str) -> Result<Vec<JsonPointer>, String> {
+    if text.is_empty() {
+        return Ok(Vec::new());
+    }
+    if let Some(pointer) = text.strip_prefix('/') {
+        return Ok(pointer
+            .split('/')
+            .map(|segment| JsonPointer::ObjectKey(segment.replace("~1", "/").replace("~0", "~")))
+            .collect());
+    }
+    let mut chars = text.strip_prefix('$').unwrap_or(text).chars().peekable();
+    let mut segments = Vec::new();
+    let mut first = true;
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => segments.push(JsonPointer::ObjectKey(bare_key(&mut chars)?)),
+            '[' => segments.push(bracketed(&mut chars)?),
+            c if first => {
+                let key = c.to_string() + &bare_key(&mut chars).unwrap_or_default();
+                segments.push(JsonPointer::ObjectKey(key));
+            }
+            c => return Err(format!("unexpected '{c}', expected '.' or '['")),
+        }
+        first = false;
+    }
+    Ok(segments)
+}
+
+/// Key after `.`, up to the next `.` or `[`
+fn bare_key(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    let mut key = String::new();
+    while let Some(&c) = chars.peek() {
+        if c == '.' || c == '[' {
+            break;
+        }
+        key.push(c);
+        chars.next();
+    }
+    if key.is_empty() {
+        return Err("empty key after '.'".to_string());
+    }
+    Ok(key)
+}
+
+/// `[0]` index or `["key"]` / `['key']` quoted key, after `[`
+fn bracketed(chars: &mut Peekable<Chars>) -> Result<JsonPointer, String> {
+    let segment = match chars.peek() {
+        Some(&quote @ ('"' | '\'')) => {
+            chars.next();
+            let mut key = String::new();
+            loop {
+                match chars.next() {
+                    Some('\\') => key.extend(chars.next()),
+                    Some(c) if c == quote => break,
+                    Some(c) => key.push(c),
+                    None => return Err(format!("missing closing {quote}")),
+                }
+            }
+            JsonPointer::ObjectKey(key)
+        }
+        _ => {
+            let mut index = String::new();
+            while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                index.push(c);
+            }
+            JsonPointer::ArrayIdx(
+                index
+                    .parse()
+                    .map_err(|_| "expected index or quoted key in []")?,
+            )
+        }
+    };
+    match chars.next() {
+        Some(']') => Ok(segment),
+        _ => Err("missing ']'".to_string()),
+    }
+}
+
+/// Resolves parsed segments against the document into tree identifiers
+pub fn resolve(root: &Value, segments: Vec<JsonPointer>) -> Result<Vec<JsonPointer>, Unresolved> {
+    let mut path = match root {
+        Value::Object(_) | Value::Array(_) => Vec::new(),
+        _ => vec![JsonPointer::None],
+    };
+    let mut value = root;
+    for segment in segments {
+        let location = match selected_json_pointer(&path) {
+            pointer if pointer.is_empty() => "the root".to_string(),
+            pointer => pointer,
+        };
+        let found = match value {
+            Value::Object(object) => {
+                let key = segment.to_string();
+                match object.get(&key) {
+                    Some(child) => Ok((child, JsonPointer::ObjectKey(key))),
+                    None => Err(format!("no key '{key}' under {location}")),
+                }
+            }
+            Value::Array(array) => match segment.to_string().parse::<usize>() {
+                Ok(index) => match array.get(index) {
+                    Some(child) => Ok((child, JsonPointer::ArrayIdx(index))),
+                    None => Err(format!(
+                        "no index {index} under {location}, it has {} elements",
+                        array.len()
+                    )),
+                },
+                Err(_) => Err(format!(
+                    "{location} is an array, '{segment}' is not an index"
+                )),
+            },
+            _ => Err(format!("{location} is a scalar, it has no '{segment}'")),
+        };
+        match found {
+            Ok((child, pointer)) => {
+                value = child;
+                path.push(pointer);
+            }
+            Err(message) => {
+                return Err(Unresolved {
+                    resolved: path,
+                    message,
+                })
+            }
+        }
+    }
+    Ok(path)
+}
+
+/// Resolves JSON Pointer or dotted path against the document, `None` when it doesn't exist
+pub fn resolve_path(root: &Value, text: &str) -> Option<Vec<JsonPointer>> {
+    resolve(root, parse_path(text).ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn resolves_pointer_and_dotted_paths() {
+        let root = json!({"data": {"items": [{"price": 5}], "a.b": true}});
+        let key = |key: &str| JsonPointer::ObjectKey(key.to_string());
+        let price = vec![
+            key("data"),
+            key("items"),
+            JsonPointer::ArrayIdx(0),
+            key("price"),
+        ];
+        for text in [
+            "/data/items/0/price",
+            "data.items[0].price",
+            "$.data['items'][0][\"price\"]",
+        ] {
+            assert_eq!(resolve_path(&root, text), Some(price.clone()), "{text}");
+        }
+        assert_eq!(
+            resolve_path(&root, "data[\"a.b\"]"),
+            Some(vec![key("data"), key("a.b")])
+        );
+        assert_eq!(resolve_path(&root, ""), Some(Vec::new()));
+
+        let err = resolve(&root, parse_path("data.items[0].cost").unwrap()).unwrap_err();
+        assert_eq!(err.message, "no key 'cost' under /data/items/0");
+        assert_eq!(err.resolved, price[..3]);
+        let err = resolve(&root, parse_path("/data/items/3").unwrap()).unwrap_err();
+        assert_eq!(
+            err.message,
+            "no index 3 under /data/items, it has 1 elements"
+        );
+        assert!(parse_path("data.items[x]").is_err());
+        assert!(parse_path("data..items").is_err());
+
+        let path = [key("data"), key("a b"), JsonPointer::ArrayIdx(0), key("id")];
+        assert_eq!(format_path(&path, PathFormat::Pointer), "/data/a b/0/id");
+        assert_eq!(format_path(&path, PathFormat::Jq), ".data[\"a b\"][0].id");
+        assert_eq!(
+            format_path(&path, PathFormat::JsonPath),
+            "$.data[\"a b\"][0].id"
+        );
+        assert_eq!(format_path(&path[2..], PathFormat::Jq), ".[0].id");
+        assert_eq!(format_path(&[], PathFormat::Jq), ".");
+        assert_eq!(format_path(&[], PathFormat::JsonPath), "$");
+    }
+
+    #[test]
+    fn escapes_pointer_keys_with_slash_and_tilde() {
+        let path = vec![
+            JsonPointer::ObjectKey("a/b".to_string()),
+            JsonPointer::ArrayPage(0, 99),
+            JsonPointer::ArrayIdx(3),
+            JsonPointer::ObjectKey("~1".to_string()),
+            JsonPointer::ObjectKey("m~n/".to_string()),
+        ];
+        let pointer = selected_json_pointer(&path);
+        assert_eq!(pointer, "/a~1b/3/~01/m~0n~1");
+        let keys: Vec<_> = parse_path(&pointer)
+            .unwrap()
+            .into_iter()
+            .map(|pointer| pointer.to_string())
+            .collect();
+        assert_eq!(keys, ["a/b", "3", "~1", "m~n/"]);
+    }
+}
@@ -0,0 +1,64 @@
+use serde_json::Value;
+use serde_json_path::{JsonPath, PathElement};
+
+use crate::JsonPointer;
+
+/// Node matched by a JSONPath query
+pub struct QueryResult {
+    pub path: Vec<JsonPointer>,
+    pub value: Value,
+}
+
+/// Evaluates JSONPath (RFC 9535) expression, results are in document order
+pub fn run_jsonpath(root: &Value, expr: &str) -> Result<Vec<QueryResult>, String> {
+    let path = JsonPath::parse(expr).map_err(|err| err.to_string())?;
+    let results = path
+        .query_located(root)
+        .into_iter()
+        .map(|node| {
+            let mut path: Vec<JsonPointer> = node
+                .location()
+                .iter()
+                .map(|element| match element {
+                    PathElement::Name(name) => JsonPointer::ObjectKey(name.to_string()),
+                    PathElement::Index(index) => JsonPointer::ArrayIdx(*index),
+                })
+                .collect();
+            // a scalar document is a single tree item
+            if path.is_empty() && !matches!(root, Value::Object(_) | Value::Array(_)) {
+                path.push(JsonPointer::None);
+            }
+            QueryResult {
+                path,
+                value: node.node().clone(),
+            }
+        })
+        .collect();
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn results_have_tree_paths() {
+        let root = json!({"store": {"book": [{"author": "A"}, {"title": "T"}, {"author": "B"}]}});
+        let results = run_jsonpath(&root, "$.store.book[*].author").unwrap();
+        let paths: Vec<_> = results.iter().map(|result| result.path.clone()).collect();
+        let book = |index| {
+            vec![
+                JsonPointer::ObjectKey("store".to_string()),
+                JsonPointer::ObjectKey("book".to_string()),
+                JsonPointer::ArrayIdx(index),
+                JsonPointer::ObjectKey("author".to_string()),
+            ]
+        };
+        assert_eq!(paths, [book(0), book(2)]);
+        assert_eq!(results[1].value, json!("B"));
+        let results = run_jsonpath(&json!(1), "$").unwrap();
+        assert_eq!(results[0].path, [JsonPointer::None]);
+        assert!(run_jsonpath(&root, "$.[").is_err());
+    }
+}
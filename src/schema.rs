@@ -0,0 +1,76 @@
+use jsonschema::Validator;
+use serde_json::Value;
+use std::{error::Error, fmt};
+
+/// Value of the document not matching the schema
+pub struct Violation {
+    /// RFC 6901 JSON Pointer of the value, empty for the whole document
+    pub pointer: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.pointer.as_str() {
+            "" => write!(f, "(root): {}", self.message),
+            pointer => write!(f, "{pointer}: {}", self.message),
+        }
+    }
+}
+
+/// Compiled JSON Schema (draft 7)
+pub struct Schema {
+    validator: Validator,
+}
+
+impl Schema {
+    pub fn compile(schema: &Value) -> Result<Self, Box<dyn Error>> {
+        let validator =
+            jsonschema::draft7::new(schema).map_err(|err| format!("invalid schema: {err}"))?;
+        Ok(Self { validator })
+    }
+
+    /// All violations, sorted by pointer
+    pub fn validate(&self, document: &Value) -> Vec<Violation> {
+        let mut violations: Vec<_> = self
+            .validator
+            .iter_errors(document)
+            .map(|err| Violation {
+                pointer: err.instance_path.to_string(),
+                message: err.to_string(),
+            })
+            .collect();
+        violations.sort_by(|a, b| a.pointer.cmp(&b.pointer));
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reports_violations_with_pointers() {
+        let schema = Schema::compile(&json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "id": {"type": "integer"},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            }
+        }))
+        .unwrap();
+        let violations = schema.validate(&json!({"id": "x", "tags": ["a", 1]}));
+        let pointers: Vec<_> = violations.iter().map(|v| v.pointer.as_str()).collect();
+        assert_eq!(pointers, ["", "/id", "/tags/1"]);
+        assert_eq!(
+            violations[0].to_string(),
+            "(root): \"name\" is a required property"
+        );
+        assert!(schema
+            .validate(&json!({"name": "n", "tags": []}))
+            .is_empty());
+        assert!(Schema::compile(&json!({"type": 1})).is_err());
+    }
+}
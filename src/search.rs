@@ -0,0 +1,815 @@
+use regex::{Regex, RegexBuilder};
+use serde_json::{Number, Value};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use crate::JsonPointer;
+
+/// Strings longer than this get the text around the match shown when jumping to them
+const CONTEXT_MIN_LEN: usize = 60;
+/// Characters shown before and after the match
+const CONTEXT_CHARS: usize = 30;
+
+/// How the query is matched when not fuzzy
+#[derive(Default)]
+pub enum SearchMode {
+    /// Substring
+    #[default]
+    Plain,
+    /// Last pattern the query compiled to
+    Regex(Regex),
+}
+
+/// Which text of the nodes is matched
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum SearchScope {
+    #[default]
+    Both,
+    /// Object keys and array indices
+    Keys,
+    /// Leaf values
+    Values,
+}
+
+impl SearchScope {
+    fn keys(self) -> bool {
+        self != Self::Values
+    }
+
+    fn values(self) -> bool {
+        self != Self::Keys
+    }
+}
+
+/// Query matching nodes by the type of their value, `:null`, `:empty-array`, ...
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TypeQuery {
+    Null,
+    Bool,
+    Number,
+    String,
+    Object,
+    Array,
+    EmptyObject,
+    EmptyArray,
+}
+
+impl TypeQuery {
+    pub fn parse(query: &str) -> Option<Self> {
+        match query.strip_prefix(':')? {
+            "null" => Some(Self::Null),
+            "bool" => Some(Self::Bool),
+            "number" => Some(Self::Number),
+            "string" => Some(Self::String),
+            "object" => Some(Self::Object),
+            "array" => Some(Self::Array),
+            "empty-object" => Some(Self::EmptyObject),
+            "empty-array" => Some(Self::EmptyArray),
+            _ => None,
+        }
+    }
+
+    fn matches(self, value: &Value) -> bool {
+        match (self, value) {
+            (Self::Null, Value::Null)
+            | (Self::Bool, Value::Bool(_))
+            | (Self::Number, Value::Number(_))
+            | (Self::String, Value::String(_))
+            | (Self::Object, Value::Object(_))
+            | (Self::Array, Value::Array(_)) => true,
+            (Self::EmptyObject, Value::Object(object)) => object.is_empty(),
+            (Self::EmptyArray, Value::Array(array)) => array.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum CompareOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+/// Query matching number leaves by comparison, `>1000`, `<=0.5`, `key=price >1000`
+#[derive(PartialEq, Debug)]
+pub struct Comparison {
+    /// Only leaves under this key
+    key: Option<String>,
+    op: CompareOp,
+    value: f64,
+    /// The value exactly, when it is an integer (beyond f64 precision too)
+    integer: Option<i128>,
+}
+
+impl Comparison {
+    /// `None` for queries that aren't an operator followed by a number, e.g. `<div` or `=>`,
+    /// those are searched as text
+    pub fn parse(query: &str) -> Option<Self> {
+        let (key, expr) = match query.strip_prefix("key=") {
+            Some(rest) => {
+                let (key, expr) = rest.split_once(' ')?;
+                (Some(key.to_string()), expr.trim_start())
+            }
+            None => (None, query),
+        };
+        let ops = [
+            (">=", CompareOp::Ge),
+            ("<=", CompareOp::Le),
+            ("!=", CompareOp::Ne),
+            ("==", CompareOp::Eq),
+            (">", CompareOp::Gt),
+            ("<", CompareOp::Lt),
+            ("=", CompareOp::Eq),
+        ];
+        let (op, number) = ops
+            .iter()
+            .find_map(|(symbol, op)| Some((*op, expr.strip_prefix(symbol)?)))?;
+        let number = number.trim();
+        let value = number
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())?;
+        Some(Self {
+            key,
+            op,
+            value,
+            integer: number.parse::<i128>().ok(),
+        })
+    }
+
+    fn matches(&self, path: &[JsonPointer], value: &Value) -> bool {
+        let Value::Number(number) = value else {
+            return false;
+        };
+        if let Some(key) = &self.key {
+            if !matches!(path.last(), Some(JsonPointer::ObjectKey(last)) if last == key) {
+                return false;
+            }
+        }
+        let ordering = match (integer(number), self.integer) {
+            (Some(number), Some(integer)) => number.cmp(&integer),
+            _ => match number
+                .as_f64()
+                .and_then(|number| number.partial_cmp(&self.value))
+            {
+                Some(ordering) => ordering,
+                None => return false,
+            },
+        };
+        match self.op {
+            CompareOp::Lt => ordering == Ordering::Less,
+            CompareOp::Le => ordering != Ordering::Greater,
+            CompareOp::Gt => ordering == Ordering::Greater,
+            CompareOp::Ge => ordering != Ordering::Less,
+            CompareOp::Eq => ordering == Ordering::Equal,
+            CompareOp::Ne => ordering != Ordering::Equal,
+        }
+    }
+}
+
+/// Exact value of an integer number, `None` for floats
+fn integer(number: &Number) -> Option<i128> {
+    number
+        .as_i64()
+        .map(i128::from)
+        .or_else(|| number.as_u64().map(i128::from))
+}
+
+#[derive(Default)]
+pub struct SearchState {
+    pub query: String,
+    pub editing: bool,
+    pub matches: Vec<Vec<JsonPointer>>,
+    pub current: Option<usize>,
+    /// Fuzzy matching, matches are ranked by score
+    pub fuzzy: bool,
+    pub mode: SearchMode,
+    /// Reset with the query
+    pub scope: SearchScope,
+    /// Plain and regex queries match regardless of case (fuzzy search always does)
+    pub ignore_case: bool,
+    /// Only descendants of this node match, reset with the query
+    pub subtree: Option<Vec<JsonPointer>>,
+    /// Why the query doesn't compile as regex
+    pub error: Option<String>,
+    matched: HashSet<Vec<JsonPointer>>,
+    scores: HashMap<Vec<JsonPointer>, u32>,
+    ancestors: HashSet<Vec<JsonPointer>>,
+}
+
+impl SearchState {
+    pub fn is_active(&self) -> bool {
+        !self.query.is_empty()
+    }
+
+    pub fn update(&mut self, root: &Value) {
+        self.error = None;
+        if let Some(query) = TypeQuery::parse(&self.query) {
+            self.matches = find_typed(root, query);
+            self.scores.clear();
+        } else if let Some(comparison) = Comparison::parse(&self.query) {
+            self.matches = find_compared(root, &comparison);
+            self.scores.clear();
+        } else {
+            self.match_text(root);
+        }
+        if let Some(subtree) = &self.subtree {
+            self.matches
+                .retain(|path| path.len() > subtree.len() && path.starts_with(subtree));
+        }
+        self.matched = self.matches.iter().cloned().collect();
+        self.ancestors = self
+            .matches
+            .iter()
+            .flat_map(|path| (1..path.len()).map(|len| path[..len].to_vec()))
+            .collect();
+        self.current = if self.matches.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+    }
+
+    /// Matches keys and values against the query text, plain, regex or fuzzy
+    fn match_text(&mut self, root: &Value) {
+        if let SearchMode::Regex(regex) = &mut self.mode {
+            match RegexBuilder::new(&self.query)
+                .case_insensitive(self.ignore_case)
+                .build()
+            {
+                Ok(compiled) => *regex = compiled,
+                Err(err) => self.error = Some(err.to_string()),
+            }
+        }
+        if self.error.is_some() {
+            self.matches.clear();
+            self.scores.clear();
+        } else if self.fuzzy {
+            let ranked = filter_items(root, &self.query, self.scope);
+            self.matches = ranked.iter().map(|(path, _)| path.clone()).collect();
+            self.scores = ranked.into_iter().collect();
+        } else {
+            self.matches = match &self.mode {
+                SearchMode::Plain => find_matches(root, &self.query, self.scope, self.ignore_case),
+                SearchMode::Regex(regex) => find_regex_matches(root, regex, self.scope),
+            };
+            self.scores.clear();
+        }
+    }
+
+    /// Clears the query, keeping the matching mode
+    pub fn clear(&mut self) {
+        *self = Self {
+            fuzzy: self.fuzzy,
+            ignore_case: self.ignore_case,
+            mode: std::mem::take(&mut self.mode),
+            ..Self::default()
+        };
+    }
+
+    /// Switches between plain and regex matching, fuzzy search is turned off by regex
+    pub fn toggle_regex(&mut self) {
+        self.mode = match self.mode {
+            SearchMode::Plain => {
+                self.fuzzy = false;
+                SearchMode::Regex(Regex::new("").expect("empty pattern compiles"))
+            }
+            SearchMode::Regex(_) => SearchMode::Plain,
+        };
+    }
+
+    /// Restricts matching to `scope`, or matches both keys and values again
+    pub fn toggle_scope(&mut self, scope: SearchScope) {
+        self.scope = if self.scope == scope {
+            SearchScope::Both
+        } else {
+            scope
+        };
+    }
+
+    pub fn is_regex(&self) -> bool {
+        matches!(self.mode, SearchMode::Regex(_))
+    }
+
+    /// Parts of `text` matched by the query, capture groups of a regex when it has any.
+    /// None in fuzzy mode, its characters match scattered, and for type queries and comparisons.
+    pub fn highlights(&self, text: &str) -> Vec<Range<usize>> {
+        if !self.is_active()
+            || self.error.is_some()
+            || self.fuzzy
+            || TypeQuery::parse(&self.query).is_some()
+            || Comparison::parse(&self.query).is_some()
+        {
+            return Vec::new();
+        }
+        let regex = match &self.mode {
+            SearchMode::Plain => return self.substring_ranges(text),
+            SearchMode::Regex(regex) => regex,
+        };
+        let first = usize::from(regex.captures_len() > 1);
+        let groups = first..regex.captures_len();
+        regex
+            .captures_iter(text)
+            .flat_map(|captures| {
+                groups
+                    .clone()
+                    .filter_map(move |group| captures.get(group))
+                    .map(|found| found.range())
+                    .collect::<Vec<_>>()
+            })
+            .filter(|range| !range.is_empty())
+            .collect()
+    }
+
+    /// Text around the first match in a long string value, telling why it matched
+    pub fn match_context(&self, text: &str) -> Option<String> {
+        if text.chars().count() <= CONTEXT_MIN_LEN {
+            return None;
+        }
+        let found = self.highlights(text).into_iter().next()?;
+        let before: Vec<char> = text[..found.start].chars().collect();
+        let skipped = before.len().saturating_sub(CONTEXT_CHARS);
+        let mut after = text[found.end..].chars();
+        let mut context = String::new();
+        if skipped > 0 {
+            context.push('…');
+        }
+        context.extend(&before[skipped..]);
+        context += &text[found];
+        context.extend(after.by_ref().take(CONTEXT_CHARS));
+        if after.next().is_some() {
+            context.push('…');
+        }
+        Some(context.replace(['\n', '\r', '\t'], " "))
+    }
+
+    fn substring_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        let (text, query) = if self.ignore_case {
+            let lowered = text.to_lowercase();
+            // positions of the lowered text would not map back
+            if lowered.len() != text.len() {
+                return Vec::new();
+            }
+            (lowered, self.query.to_lowercase())
+        } else {
+            (text.to_string(), self.query.clone())
+        };
+        text.match_indices(&query)
+            .map(|(start, found)| start..start + found.len())
+            .collect()
+    }
+
+    pub fn is_match(&self, path: &[JsonPointer]) -> bool {
+        self.matched.contains(path)
+    }
+
+    pub fn contains_match(&self, path: &[JsonPointer]) -> bool {
+        self.ancestors.contains(path)
+    }
+
+    /// Fuzzy score of the node, `None` when it doesn't match or not in fuzzy mode
+    pub fn score(&self, path: &[JsonPointer]) -> Option<u32> {
+        self.scores.get(path).copied()
+    }
+
+    pub fn current_match(&self) -> Option<&[JsonPointer]> {
+        self.current.map(|index| self.matches[index].as_slice())
+    }
+
+    pub fn next(&mut self) -> Option<&[JsonPointer]> {
+        let count = self.matches.len();
+        self.current = self.current.map(|index| (index + 1) % count);
+        self.current_match()
+    }
+
+    pub fn prev(&mut self) -> Option<&[JsonPointer]> {
+        let count = self.matches.len();
+        self.current = self.current.map(|index| (index + count - 1) % count);
+        self.current_match()
+    }
+}
+
+/// Text of a leaf as it is matched against the query, `None` for objects and arrays
+pub fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Object(_) | Value::Array(_) => None,
+        _ => Some(value.to_string()),
+    }
+}
+
+/// Key of the node as shown in the tree (array index for elements), empty for the root
+fn key_text(path: &[JsonPointer]) -> String {
+    path.last().map(ToString::to_string).unwrap_or_default()
+}
+
+/// Paths of all nodes whose key or leaf value contains `query`, in tree order
+pub fn find_matches(
+    root: &Value,
+    query: &str,
+    scope: SearchScope,
+    ignore_case: bool,
+) -> Vec<Vec<JsonPointer>> {
+    let mut matches = Vec::new();
+    if query.is_empty() {
+        return matches;
+    }
+    let fold = |text: String| {
+        if ignore_case {
+            text.to_lowercase()
+        } else {
+            text
+        }
+    };
+    let needle = fold(query.to_string());
+    visit(root, &mut root_path(root), &mut |path, value| {
+        let key_match = scope.keys() && fold(key_text(path)).contains(&needle);
+        let value_match =
+            scope.values() && scalar_text(value).is_some_and(|text| fold(text).contains(&needle));
+        if key_match || value_match {
+            matches.push(path.to_vec());
+        }
+    });
+    matches
+}
+
+/// Paths of all nodes below the root whose value is of the queried type, in tree order
+pub fn find_typed(root: &Value, query: TypeQuery) -> Vec<Vec<JsonPointer>> {
+    let mut matches = Vec::new();
+    visit(root, &mut root_path(root), &mut |path, value| {
+        if !path.is_empty() && query.matches(value) {
+            matches.push(path.to_vec());
+        }
+    });
+    matches
+}
+
+/// Paths of all number leaves passing the comparison, in tree order
+pub fn find_compared(root: &Value, comparison: &Comparison) -> Vec<Vec<JsonPointer>> {
+    let mut matches = Vec::new();
+    visit(root, &mut root_path(root), &mut |path, value| {
+        if comparison.matches(path, value) {
+            matches.push(path.to_vec());
+        }
+    });
+    matches
+}
+
+/// Paths of all object members named `key`, in tree order
+pub fn find_key(root: &Value, key: &str) -> Vec<Vec<JsonPointer>> {
+    let mut found = Vec::new();
+    visit(root, &mut root_path(root), &mut |path, _| {
+        if matches!(path.last(), Some(JsonPointer::ObjectKey(last)) if last == key) {
+            found.push(path.to_vec());
+        }
+    });
+    found
+}
+
+/// Paths of all nodes whose key or leaf value matches `regex`, in tree order
+pub fn find_regex_matches(
+    root: &Value,
+    regex: &Regex,
+    scope: SearchScope,
+) -> Vec<Vec<JsonPointer>> {
+    let mut matches = Vec::new();
+    if regex.as_str().is_empty() {
+        return matches;
+    }
+    visit(root, &mut root_path(root), &mut |path, value| {
+        let key_match = scope.keys()
+            && path
+                .last()
+                .is_some_and(|key| *key != JsonPointer::None && regex.is_match(&key.to_string()));
+        let value_match =
+            scope.values() && scalar_text(value).is_some_and(|text| regex.is_match(&text));
+        if key_match || value_match {
+            matches.push(path.to_vec());
+        }
+    });
+    matches
+}
+
+/// Paths of all nodes whose key or leaf value fuzzy-matches `query` with their best score,
+/// best first (tree order among equal scores)
+pub fn filter_items(root: &Value, query: &str, scope: SearchScope) -> Vec<(Vec<JsonPointer>, u32)> {
+    let mut ranked = Vec::new();
+    if query.is_empty() {
+        return ranked;
+    }
+    visit(root, &mut root_path(root), &mut |path, value| {
+        let key_score = scope
+            .keys()
+            .then(|| fuzzy_score(query, &key_text(path)))
+            .flatten();
+        let value_score = scope
+            .values()
+            .then(|| scalar_text(value).and_then(|text| fuzzy_score(query, &text)))
+            .flatten();
+        if let Some(score) = key_score.max(value_score) {
+            ranked.push((path.to_vec(), score));
+        }
+    });
+    ranked.sort_by(|(_, a), (_, b)| b.cmp(a));
+    ranked
+}
+
+const CONSECUTIVE_BONUS: u32 = 5;
+const WORD_START_BONUS: u32 = 3;
+
+/// Scores `haystack` containing the characters of `needle` in order (case-insensitive),
+/// with bonuses for consecutive characters and characters starting a word
+pub fn fuzzy_score(needle: &str, haystack: &str) -> Option<u32> {
+    let mut needle = needle.chars().flat_map(char::to_lowercase).peekable();
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+    let mut matched_previous = false;
+    for c in haystack.chars() {
+        let Some(&wanted) = needle.peek() else {
+            break;
+        };
+        let matched = c.to_lowercase().eq(wanted.to_lowercase());
+        if matched {
+            needle.next();
+            score += 1;
+            if matched_previous {
+                score += CONSECUTIVE_BONUS;
+            }
+            let word_start = match previous {
+                None => true,
+                Some(previous) => {
+                    !previous.is_alphanumeric() || (previous.is_lowercase() && c.is_uppercase())
+                }
+            };
+            if word_start {
+                score += WORD_START_BONUS;
+            }
+        }
+        matched_previous = matched;
+        previous = Some(c);
+    }
+    needle.peek().is_none().then_some(score)
+}
+
+/// Path of the root node, scalar root is shown as a single item
+fn root_path(root: &Value) -> Vec<JsonPointer> {
+    match root {
+        Value::Object(_) | Value::Array(_) => Vec::new(),
+        _ => vec![JsonPointer::None],
+    }
+}
+
+/// Calls `f` for every node of the document in tree order
+fn visit(value: &Value, path: &mut Vec<JsonPointer>, f: &mut dyn FnMut(&[JsonPointer], &Value)) {
+    f(path, value);
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                path.push(JsonPointer::ObjectKey(key.clone()));
+                visit(value, path, f);
+                path.pop();
+            }
+        }
+        Value::Array(array) => {
+            for (index, value) in array.iter().enumerate() {
+                path.push(JsonPointer::ArrayIdx(index));
+                visit(value, path, f);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn regex_mode_matches_and_highlights_groups() {
+        let root = json!({"id": "user-42", "tags": ["x", "user-7"], "count": 42});
+        let mut search = SearchState::default();
+        search.toggle_regex();
+        search.query = r"user-(\d+)".to_string();
+        search.update(&root);
+        let key = |key: &str| JsonPointer::ObjectKey(key.to_string());
+        assert_eq!(
+            search.matches,
+            [vec![key("id")], vec![key("tags"), JsonPointer::ArrayIdx(1)]]
+        );
+        assert_eq!(search.highlights("user-42, user-7"), [5..7, 14..15]);
+
+        search.query = r"^\d$".to_string();
+        search.update(&root);
+        assert_eq!(
+            search.matches,
+            [
+                vec![key("tags"), JsonPointer::ArrayIdx(0)],
+                vec![key("tags"), JsonPointer::ArrayIdx(1)]
+            ]
+        );
+
+        search.toggle_scope(SearchScope::Values);
+        search.update(&root);
+        assert!(search.matches.is_empty());
+
+        search.query = "user-(".to_string();
+        search.update(&root);
+        assert!(search.error.is_some());
+        assert!(search.matches.is_empty());
+    }
+
+    #[test]
+    fn ignore_case_applies_to_plain_and_regex() {
+        let root = json!({"customerId": 1, "CustomerID": 2, "customer_id": 3});
+        let mut search = SearchState::default();
+        search.toggle_scope(SearchScope::Keys);
+        search.query = "customerid".to_string();
+        search.update(&root);
+        assert!(search.matches.is_empty());
+        search.ignore_case = true;
+        search.update(&root);
+        assert_eq!(search.matches.len(), 2);
+        assert_eq!(search.highlights("CustomerID, customerId"), [0..10, 12..22]);
+
+        search.toggle_regex();
+        search.query = "^customer_?id$".to_string();
+        search.update(&root);
+        assert_eq!(search.matches.len(), 3);
+        search.clear();
+        assert!(search.ignore_case);
+        assert!(search.is_regex());
+    }
+
+    #[test]
+    fn subtree_search_matches_descendants_only() {
+        let root = json!({"orders": [{"id": 1, "item": {"id": 5}}, {"id": 2}], "id": 0});
+        let key = |key: &str| JsonPointer::ObjectKey(key.to_string());
+        let mut search = SearchState::default();
+        search.toggle_scope(SearchScope::Keys);
+        search.query = "id".to_string();
+        search.update(&root);
+        assert_eq!(search.matches.len(), 4);
+
+        let order = vec![key("orders"), JsonPointer::ArrayIdx(0)];
+        search.subtree = Some(order.clone());
+        search.update(&root);
+        assert_eq!(
+            search.matches,
+            [
+                [order.clone(), vec![key("id")]].concat(),
+                [order, vec![key("item"), key("id")]].concat()
+            ]
+        );
+        search.subtree = Some(vec![key("orders"), JsonPointer::ArrayIdx(1), key("id")]);
+        search.update(&root);
+        assert!(search.matches.is_empty());
+        search.clear();
+        assert!(search.subtree.is_none());
+    }
+
+    #[test]
+    fn type_queries_match_by_value_type() {
+        let root = json!({
+            "user": {"name": "Ann", "email": null, "tags": [], "meta": {}},
+            "orders": [{"total": 5, "paid": true, "note": null}, []]
+        });
+        let key = |key: &str| JsonPointer::ObjectKey(key.to_string());
+        let mut search = SearchState::default();
+        let mut matches = |query: &str| {
+            search.query = query.to_string();
+            search.update(&root);
+            search.matches.clone()
+        };
+        assert_eq!(
+            matches(":null"),
+            [
+                vec![key("user"), key("email")],
+                vec![key("orders"), JsonPointer::ArrayIdx(0), key("note")]
+            ]
+        );
+        assert_eq!(
+            matches(":empty-array"),
+            [
+                vec![key("user"), key("tags")],
+                vec![key("orders"), JsonPointer::ArrayIdx(1)]
+            ]
+        );
+        assert_eq!(matches(":empty-object"), [vec![key("user"), key("meta")]]);
+        assert_eq!(matches(":bool").len(), 1);
+        assert_eq!(matches(":number").len(), 1);
+        assert_eq!(matches(":object").len(), 3);
+        assert_eq!(matches(":array").len(), 3);
+        // not a type, searched as text
+        assert_eq!(TypeQuery::parse(":nul"), None);
+        assert!(search.highlights("null").is_empty());
+    }
+
+    #[test]
+    fn finds_members_with_same_key() {
+        let root = json!({"orders": [{"customer_id": 1}, {"id": 2}, {"x": {"customer_id": 3}}]});
+        let key = |key: &str| JsonPointer::ObjectKey(key.to_string());
+        let orders = |index| vec![key("orders"), JsonPointer::ArrayIdx(index)];
+        assert_eq!(
+            find_key(&root, "customer_id"),
+            [
+                [orders(0), vec![key("customer_id")]].concat(),
+                [orders(2), vec![key("x"), key("customer_id")]].concat()
+            ]
+        );
+        assert!(find_key(&root, "0").is_empty());
+    }
+
+    #[test]
+    fn shows_context_of_match_in_long_string() {
+        let long = format!(
+            "{}needle in\nthe middle{}",
+            "a".repeat(100),
+            "b".repeat(100)
+        );
+        let root = json!({"short": "needle", "long": long});
+        let mut search = SearchState {
+            query: "needle".to_string(),
+            ..SearchState::default()
+        };
+        search.update(&root);
+        assert_eq!(search.matches.len(), 2);
+        assert_eq!(search.match_context("needle"), None);
+        assert_eq!(
+            search.match_context(&long).unwrap(),
+            format!("…{}needle in the middle{}…", "a".repeat(30), "b".repeat(16))
+        );
+        search.query = ":string".to_string();
+        search.update(&root);
+        assert_eq!(search.match_context(&long), None);
+    }
+
+    #[test]
+    fn comparisons_match_number_leaves() {
+        let root = json!({
+            "items": [{"price": 1500, "qty": 2}, {"price": 0.5, "qty": 1500}, {"price": "2000"}],
+            "id": 9007199254740993u64
+        });
+        let key = |key: &str| JsonPointer::ObjectKey(key.to_string());
+        let mut search = SearchState::default();
+        let mut matches = |query: &str| {
+            search.query = query.to_string();
+            search.update(&root);
+            (search.matches.clone(), search.error.clone())
+        };
+        let price = |index| vec![key("items"), JsonPointer::ArrayIdx(index), key("price")];
+        assert_eq!(matches(">1000").0.len(), 3);
+        assert_eq!(matches("key=price >1000"), (vec![price(0)], None));
+        assert_eq!(matches("<=0.5"), (vec![price(1)], None));
+        assert_eq!(matches("key=qty != 2").0.len(), 1);
+        // beyond f64 precision, 9007199254740992 as f64
+        assert_eq!(matches("=9007199254740993").0, [vec![key("id")]]);
+        assert!(matches("==9007199254740992").0.is_empty());
+
+        assert_eq!(Comparison::parse("price"), None);
+    }
+
+    #[test]
+    fn operators_without_number_are_searched_as_text() {
+        let root = json!({"html": "<div>", "arrow": "x => y", "prompt": ">>> ", "n": 5});
+        let key = |key: &str| JsonPointer::ObjectKey(key.to_string());
+        let mut search = SearchState::default();
+        for (query, found) in [
+            ("<div", key("html")),
+            ("=>", key("arrow")),
+            (">>>", key("prompt")),
+        ] {
+            assert_eq!(Comparison::parse(query), None, "{query}");
+            search.query = query.to_string();
+            search.update(&root);
+            assert_eq!(search.error, None, "{query}");
+            assert_eq!(search.matches, [vec![found]], "{query}");
+        }
+        assert_eq!(Comparison::parse("key=price >abc"), None);
+        assert_eq!(Comparison::parse("key=price"), None);
+        assert!(Comparison::parse("> 4").is_some());
+    }
+
+    #[test]
+    fn fuzzy_scores_prefix_over_scattered() {
+        let prefix = fuzzy_score("name", "name_first").unwrap();
+        let word_start = fuzzy_score("fn", "first_name").unwrap();
+        let scattered = fuzzy_score("name", "snake_game").unwrap();
+        assert!(prefix > scattered, "{prefix} <= {scattered}");
+        assert!(word_start > fuzzy_score("fn", "often").unwrap());
+        assert_eq!(fuzzy_score("NAME", "name_first"), Some(prefix));
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+
+        assert_eq!(fuzzy_score("name", "nam"), None);
+        assert_eq!(fuzzy_score("ba", "abc"), None);
+        assert_eq!(fuzzy_score("x", ""), None);
+    }
+}
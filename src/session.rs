@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{undo::UndoStack, JsonPointer};
+
+/// Expansion state and cursor of a document, restored when the same file is opened again
+#[derive(Default, Serialize, Deserialize)]
+pub struct SessionState {
+    pub open_nodes: Vec<Vec<JsonPointer>>,
+    pub selected: Vec<JsonPointer>,
+    /// Edits not written back to the file, kept only until the viewer exits normally
+    #[serde(default, skip_serializing_if = "UndoStack::is_empty")]
+    pub edits: UndoStack,
+}
+
+/// `$XDG_DATA_HOME/json_viewer`, or under `~/.local/share`
+pub fn data_dir() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+    Some(dir.join(env!("CARGO_PKG_NAME")))
+}
+
+/// Hash of the absolute path of the document, data about it is stored under. The same
+/// across Rust releases, unlike `DefaultHasher`, so stored files stay found.
+pub fn document_key(path: &Path) -> Option<String> {
+    let path = path.canonicalize().ok()?;
+    Some(format!(
+        "{:016x}",
+        fnv1a(path.as_os_str().as_encoded_bytes())
+    ))
+}
+
+/// 64-bit FNV-1a hash
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Session file of the document
+fn session_path(path: &Path) -> Option<PathBuf> {
+    Some(
+        data_dir()?
+            .join("sessions")
+            .join(format!("{}.json", document_key(path)?)),
+    )
+}
+
+/// Saved session of the document, `None` when there is none or it can't be read
+pub fn load_session(path: &Path) -> Option<SessionState> {
+    let text = fs::read_to_string(session_path(path)?).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+pub fn save_session(path: &Path, session: &SessionState) -> Result<(), Box<dyn Error>> {
+    let file = session_path(path).ok_or("no session directory")?;
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(file, serde_json::to_string(session)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_documents_by_stable_hash() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+        let key = document_key(Path::new("Cargo.toml")).unwrap();
+        assert_eq!(key.len(), 16);
+        assert_eq!(document_key(Path::new("./Cargo.toml")), Some(key));
+        assert_eq!(document_key(Path::new("missing.json")), None);
+    }
+}
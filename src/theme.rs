@@ -0,0 +1,107 @@
+use ratatui::style::Color;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use std::{fs, path::Path};
+
+/// Themes selectable by name with `--theme`
+const BUNDLED: [(&str, &str); 2] = [
+    ("default", include_str!("../themes/default.toml")),
+    ("solarized", include_str!("../themes/solarized.toml")),
+];
+
+/// Theme file, `[colors]` not given in it keep the defaults
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    pub colors: Colors,
+}
+
+macro_rules! colors {
+    ($($name:ident = $color:expr,)*) => {
+        /// Colors by their semantic names
+        #[derive(Clone, Copy, Deserialize)]
+        #[serde(default, deny_unknown_fields)]
+        pub struct Colors {
+            $(
+                #[serde(deserialize_with = "deserialize_color")]
+                pub $name: Color,
+            )*
+        }
+
+        impl Default for Colors {
+            fn default() -> Self {
+                Self {
+                    $($name: $color,)*
+                }
+            }
+        }
+    };
+}
+
+colors! {
+    key_color = Color::Reset,
+    string_color = Color::Green,
+    number_color = Color::Cyan,
+    bool_color = Color::Yellow,
+    null_color = Color::DarkGray,
+    highlight_fg = Color::Black,
+    highlight_bg = Color::Gray,
+    border_color = Color::Reset,
+}
+
+impl Colors {
+    /// Scalars by their type, object and array names as keys
+    pub fn value_color(&self, value: &Value) -> Color {
+        match value {
+            Value::String(_) => self.string_color,
+            Value::Number(_) => self.number_color,
+            Value::Bool(_) => self.bool_color,
+            Value::Null => self.null_color,
+            Value::Object(_) | Value::Array(_) => self.key_color,
+        }
+    }
+}
+
+/// Loads a bundled theme by its name, or else a theme file
+pub fn load_theme(name_or_path: &str) -> Result<Theme, String> {
+    let text = match BUNDLED.iter().find(|(name, _)| *name == name_or_path) {
+        Some((_, text)) => text.to_string(),
+        None => fs::read_to_string(Path::new(name_or_path))
+            .map_err(|err| format!("{name_or_path}: {err}"))?,
+    };
+    toml::from_str(&text).map_err(|err| format!("{name_or_path}: {err}"))
+}
+
+/// Named ratatui color (case and `-`/`_` insensitive, e.g. `"Green"` or `"light-blue"`),
+/// 24-bit `"#rrggbb"` or 256-color palette index
+pub fn parse_color(s: &str) -> Result<Color, String> {
+    s.parse().map_err(|_| format!("unknown color '{s}'"))
+}
+
+fn deserialize_color<'de, D>(deserializer: D) -> Result<Color, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let name = String::deserialize(deserializer)?;
+    parse_color(&name).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_named_and_hex_colors() {
+        assert_eq!(parse_color("Green"), Ok(Color::Green));
+        assert_eq!(parse_color("dark-gray"), Ok(Color::DarkGray));
+        assert_eq!(parse_color("#00ff88"), Ok(Color::Rgb(0, 0xff, 0x88)));
+        assert!(parse_color("#00ff8").is_err());
+        assert!(parse_color("greenish").is_err());
+        for (name, _) in BUNDLED {
+            assert!(load_theme(name).is_ok(), "{name}");
+        }
+        let theme: Theme = toml::from_str("[colors]\nnull_color = \"#808080\"").unwrap();
+        assert_eq!(theme.colors.null_color, Color::Rgb(0x80, 0x80, 0x80));
+        assert_eq!(theme.colors.string_color, Color::Green);
+    }
+}
@@ -0,0 +1,1083 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use ratatui::{
+    prelude::*,
+    style::{Color, Modifier, Style},
+};
+use serde_json::Value;
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    collections::HashSet,
+    fmt,
+    ops::{Range, RangeInclusive},
+};
+use tui_tree_widget::{TreeItem, TreeState};
+
+use crate::{get_value_at_path, theme::Colors, JsonPointer};
+
+/// Style of the tree item at given path
+pub type ItemStyle<'s> = &'s dyn Fn(&[JsonPointer]) -> Style;
+
+/// Extra text shown after the tree item at given path
+pub type ItemAnnotation<'s> = &'s dyn Fn(&[JsonPointer]) -> Option<String>;
+
+/// Text shown instead of the leaf value at given path
+pub type ItemValue<'s> = &'s dyn Fn(&[JsonPointer]) -> Option<String>;
+
+/// Parts of a key or value text to highlight
+pub type ItemHighlight<'s> = &'s dyn Fn(&str) -> Vec<Range<usize>>;
+
+/// Whether the tree item at given path is shown
+pub type ItemVisible<'s> = &'s dyn Fn(&[JsonPointer]) -> bool;
+
+/// Whether the object or array at given path has its children not read yet
+pub type ItemUnloaded<'s> = &'s dyn Fn(&[JsonPointer]) -> bool;
+
+/// Arrays longer than this are split into pages of as many elements
+pub const DEFAULT_PAGE_SIZE: usize = 1000;
+
+/// Characters of the preview after collapsed objects and arrays with `--compact`
+pub const DEFAULT_PREVIEW_CHARS: usize = 40;
+
+/// Background of highlighted parts of keys and values
+const HIGHLIGHT_BG: Color = Color::Magenta;
+
+/// Order of object members in the tree
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum SortMode {
+    /// As stored in the document
+    #[default]
+    Document,
+    KeyAscending,
+    KeyDescending,
+    /// Scalars first, then arrays, then objects
+    ValueType,
+}
+
+impl fmt::Display for SortMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Document => write!(f, "document order"),
+            Self::KeyAscending => write!(f, "key ascending"),
+            Self::KeyDescending => write!(f, "key descending"),
+            Self::ValueType => write!(f, "value type"),
+        }
+    }
+}
+
+/// How numbers are shown in the tree
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum NumberFormat {
+    /// As written in the document
+    #[default]
+    Raw,
+    /// Thousands separated, `1,234,567.89`
+    Locale,
+    /// `1.235e6`
+    Scientific,
+    /// `1.2M`, `3.4B`
+    HumanReadable,
+}
+
+impl NumberFormat {
+    /// Format after this one, back to `Raw` after the last
+    pub fn next(self) -> Self {
+        match self {
+            Self::Raw => Self::Locale,
+            Self::Locale => Self::Scientific,
+            Self::Scientific => Self::HumanReadable,
+            Self::HumanReadable => Self::Raw,
+        }
+    }
+}
+
+impl fmt::Display for NumberFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Raw => write!(f, "raw"),
+            Self::Locale => write!(f, "locale"),
+            Self::Scientific => write!(f, "scientific"),
+            Self::HumanReadable => write!(f, "human readable"),
+        }
+    }
+}
+
+/// Builds tree items out of the document
+pub struct ItemsBuilder<'s> {
+    style: ItemStyle<'s>,
+    sort: SortMode,
+    annotation: Option<ItemAnnotation<'s>>,
+    value_text: Option<ItemValue<'s>>,
+    highlight: Option<ItemHighlight<'s>>,
+    /// Items left out with their subtrees, all are shown without it
+    visible: Option<ItemVisible<'s>>,
+    /// Opened tree items, pages of long arrays get their elements built only when opened
+    opened: Option<&'s HashSet<Vec<JsonPointer>>>,
+    /// Children of all objects and arrays are built only when opened
+    lazy: bool,
+    unloaded: Option<ItemUnloaded<'s>>,
+    /// 0 for no pages
+    page_size: usize,
+    /// Some array got split into pages
+    paginated: Cell<bool>,
+    /// Numbers looking like Unix timestamps are shown as dates
+    dates: bool,
+    /// Characters of the content preview after collapsed objects and arrays, 0 for none
+    preview: usize,
+    numbers: NumberFormat,
+    colors: Colors,
+}
+
+impl<'s> ItemsBuilder<'s> {
+    pub fn new(style: ItemStyle<'s>) -> Self {
+        Self {
+            style,
+            sort: SortMode::default(),
+            annotation: None,
+            value_text: None,
+            highlight: None,
+            visible: None,
+            opened: None,
+            lazy: false,
+            unloaded: None,
+            page_size: 0,
+            paginated: Cell::new(false),
+            dates: false,
+            preview: 0,
+            numbers: NumberFormat::default(),
+            colors: Colors::default(),
+        }
+    }
+
+    pub fn sort(mut self, sort: SortMode) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    pub fn annotation(mut self, annotation: ItemAnnotation<'s>) -> Self {
+        self.annotation = Some(annotation);
+        self
+    }
+
+    pub fn value_text(mut self, value_text: ItemValue<'s>) -> Self {
+        self.value_text = Some(value_text);
+        self
+    }
+
+    pub fn highlight(mut self, highlight: ItemHighlight<'s>) -> Self {
+        self.highlight = Some(highlight);
+        self
+    }
+
+    pub fn opened(mut self, opened: &'s HashSet<Vec<JsonPointer>>) -> Self {
+        self.opened = Some(opened);
+        self
+    }
+
+    pub fn lazy(mut self, lazy: bool) -> Self {
+        self.lazy = lazy;
+        self
+    }
+
+    pub fn unloaded(mut self, unloaded: ItemUnloaded<'s>) -> Self {
+        self.unloaded = Some(unloaded);
+        self
+    }
+
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Whether the last build split an array into pages, so opening items needs a rebuild
+    pub fn paginated(&self) -> bool {
+        self.paginated.get()
+    }
+
+    fn is_opened(&self, path: &[JsonPointer]) -> bool {
+        self.opened.is_some_and(|opened| opened.contains(path))
+    }
+
+    fn is_unloaded(&self, path: &[JsonPointer]) -> bool {
+        self.unloaded.is_some_and(|unloaded| unloaded(path))
+    }
+
+    pub fn colors(mut self, colors: Colors) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    pub fn timestamps_as_dates(mut self, dates: bool) -> Self {
+        self.dates = dates;
+        self
+    }
+
+    pub fn preview(mut self, max_chars: usize) -> Self {
+        self.preview = max_chars;
+        self
+    }
+
+    pub fn visible(mut self, visible: ItemVisible<'s>) -> Self {
+        self.visible = Some(visible);
+        self
+    }
+
+    /// Item at the tree path is shown, it goes by the node in the document
+    fn is_visible(&self, tree_path: &[JsonPointer]) -> bool {
+        match self.visible {
+            Some(visible) => visible(&document_path(tree_path)),
+            None => true,
+        }
+    }
+
+    pub fn number_format(mut self, numbers: NumberFormat) -> Self {
+        self.numbers = numbers;
+        self
+    }
+
+    fn leaf_text(&self, path: &[JsonPointer], value: &Value) -> String {
+        if let Some(text) = self.value_text.and_then(|value_text| value_text(path)) {
+            return text;
+        }
+        match (value.as_i64().and_then(timestamp_date), value) {
+            (Some(date), _) if self.dates => date,
+            (_, Value::Number(number)) => format_number(&number.to_string(), self.numbers),
+            _ => value.to_string(),
+        }
+    }
+
+    pub fn build(&self, root: &Value) -> Vec<TreeItem<'static, JsonPointer>> {
+        let mut path = Vec::new();
+        match root {
+            Value::Object(object) => self.tree_items_obj(&mut path, object),
+            Value::Array(array) => self.tree_items_arr(&mut path, array),
+            _ => {
+                let path = [JsonPointer::None];
+                let item_style = (self.style)(&path);
+                let shown = self.leaf_text(&path, root);
+                let ranges = self.value_ranges(root, &shown);
+                let text =
+                    Line::from(self.highlighted(shown, self.value_style(root, item_style), ranges));
+                vec![TreeItem::new_leaf(
+                    JsonPointer::None,
+                    self.annotate(&path, text),
+                )]
+            }
+        }
+    }
+
+    fn tree_items(
+        &self,
+        tree_path: &mut Vec<JsonPointer>,
+        value: &Value,
+    ) -> TreeItem<'static, JsonPointer> {
+        let key = tree_path.last().cloned().unwrap_or_default();
+        // style and annotations go by the node in the document
+        let path = document_path(tree_path);
+        let path = path.as_ref();
+        let item_style = (self.style)(path);
+        match value {
+            Value::Object(_) | Value::Array(_) => {
+                let mut spans = self.highlighted(
+                    key.to_string(),
+                    self.value_style(value, item_style),
+                    self.key_ranges(&key),
+                );
+                let unloaded = self.is_unloaded(path);
+                let count = match value {
+                    // size isn't known before reading it
+                    Value::Object(_) if unloaded => " {…}".to_string(),
+                    Value::Array(_) if unloaded => " […]".to_string(),
+                    _ => child_count(value),
+                };
+                spans.push(Span::styled(count, Style::new().fg(Color::DarkGray)));
+                let empty = matches!(value, Value::Object(o) if o.is_empty())
+                    || matches!(value, Value::Array(a) if a.is_empty());
+                if self.preview > 0 && !empty && !self.is_opened(tree_path) {
+                    spans.push(Span::styled(
+                        format!(" {}", compact_preview(value, self.preview)),
+                        Style::new()
+                            .fg(Color::DarkGray)
+                            .add_modifier(Modifier::ITALIC),
+                    ));
+                }
+                let text = Line::from(spans);
+                let text = self.annotate(path, text);
+                TreeItem::new(key, text, self.children(tree_path, value)).unwrap()
+            }
+            _ => {
+                let mut spans = self.highlighted(
+                    format!("{key}: "),
+                    Style::new().fg(self.colors.key_color).patch(item_style),
+                    self.key_ranges(&key),
+                );
+                let shown = self.leaf_text(path, value);
+                let ranges = self.value_ranges(value, &shown);
+                spans.extend(self.highlighted(shown, self.value_style(value, item_style), ranges));
+                let text = Line::from(spans);
+                TreeItem::new_leaf(key, self.annotate(path, text))
+            }
+        }
+    }
+
+    fn key_ranges(&self, key: &JsonPointer) -> Vec<Range<usize>> {
+        match self.highlight {
+            Some(highlight) if *key != JsonPointer::None => highlight(&key.to_string()),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Highlighted parts of the value as shown, none when it is shown other than as JSON
+    /// (dates, decoded base64)
+    fn value_ranges(&self, value: &Value, shown: &str) -> Vec<Range<usize>> {
+        let Some(highlight) = self.highlight else {
+            return Vec::new();
+        };
+        match value {
+            // quoted, positions hold unless escapes moved them
+            Value::String(text)
+                if shown.len() == text.len() + 2 && shown[1..].starts_with(text.as_str()) =>
+            {
+                highlight(text)
+                    .into_iter()
+                    .map(|range| range.start + 1..range.end + 1)
+                    .collect()
+            }
+            Value::String(_) => Vec::new(),
+            _ if shown == value.to_string().as_str() => highlight(shown),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Splits `text` into spans, the parts in `ranges` get highlighted background
+    fn highlighted(
+        &self,
+        text: String,
+        style: Style,
+        ranges: Vec<Range<usize>>,
+    ) -> Vec<Span<'static>> {
+        if ranges.is_empty() {
+            return vec![Span::styled(text, style)];
+        }
+        let mut spans = Vec::new();
+        let mut end = 0;
+        for range in ranges {
+            if range.start < end || range.end > text.len() {
+                continue;
+            }
+            if range.start > end {
+                spans.push(Span::styled(text[end..range.start].to_string(), style));
+            }
+            spans.push(Span::styled(
+                text[range.clone()].to_string(),
+                style.bg(HIGHLIGHT_BG),
+            ));
+            end = range.end;
+        }
+        if end < text.len() {
+            spans.push(Span::styled(text[end..].to_string(), style));
+        }
+        spans
+    }
+
+    /// Type color of the value with the item style (e.g. search highlight) on top
+    fn value_style(&self, value: &Value, item_style: Style) -> Style {
+        let style = Style::new().fg(self.colors.value_color(value));
+        match value {
+            Value::Object(_) | Value::Array(_) => style.add_modifier(Modifier::BOLD),
+            _ => style,
+        }
+        .patch(item_style)
+    }
+
+    fn annotate(&self, path: &[JsonPointer], mut text: Line<'static>) -> Line<'static> {
+        if let Some(annotation) = self.annotation.and_then(|annotation| annotation(path)) {
+            text.spans
+                .push(Span::raw(format!("  {annotation}")).dark_gray());
+        }
+        text
+    }
+
+    fn children(
+        &self,
+        path: &mut Vec<JsonPointer>,
+        value: &Value,
+    ) -> Vec<TreeItem<'static, JsonPointer>> {
+        match value {
+            // read once opened, the placeholder keeps it expandable meanwhile
+            _ if self.is_unloaded(&document_path(path)) => {
+                vec![TreeItem::new_leaf(JsonPointer::None, "…")]
+            }
+            Value::Object(object) if object.is_empty() => Vec::new(),
+            Value::Array(array) if array.is_empty() => Vec::new(),
+            _ if self.lazy && !self.is_opened(path) => {
+                // placeholder keeping a not yet built node expandable
+                vec![TreeItem::new_leaf(JsonPointer::None, "…")]
+            }
+            Value::Object(object) => self.tree_items_obj(path, object),
+            Value::Array(array) => self.tree_items_arr(path, array),
+            _ => Vec::new(),
+        }
+    }
+
+    fn tree_items_obj(
+        &self,
+        path: &mut Vec<JsonPointer>,
+        object: &serde_json::Map<String, Value>,
+    ) -> Vec<TreeItem<'static, JsonPointer>> {
+        assert!(object.len() < usize::MAX);
+        let mut members: Vec<_> = object.iter().collect();
+        match self.sort {
+            SortMode::Document => {}
+            SortMode::KeyAscending => members.sort_by_key(|(key, _)| *key),
+            SortMode::KeyDescending => members.sort_by(|(a, _), (b, _)| b.cmp(a)),
+            SortMode::ValueType => members.sort_by_key(|(_, value)| match value {
+                Value::Object(_) => 2,
+                Value::Array(_) => 1,
+                _ => 0,
+            }),
+        }
+        members
+            .into_iter()
+            .filter_map(|(key, value)| {
+                path.push(JsonPointer::ObjectKey(key.clone()));
+                let item = self.is_visible(path).then(|| self.tree_items(path, value));
+                path.pop();
+                item
+            })
+            .collect()
+    }
+
+    fn tree_items_arr(
+        &self,
+        path: &mut Vec<JsonPointer>,
+        array: &[Value],
+    ) -> Vec<TreeItem<'static, JsonPointer>> {
+        assert!(array.len() < usize::MAX);
+        if self.page_size > 0 && array.len() > self.page_size {
+            return self.paginate_array(path, array, self.page_size);
+        }
+        self.elements(path, array, 0..=array.len().saturating_sub(1))
+    }
+
+    /// Page items of `page_size` elements each, elements of a page are built once it is opened
+    fn paginate_array(
+        &self,
+        path: &mut Vec<JsonPointer>,
+        array: &[Value],
+        page_size: usize,
+    ) -> Vec<TreeItem<'static, JsonPointer>> {
+        self.paginated.set(true);
+        (0..array.len())
+            .step_by(page_size)
+            .filter_map(|first| {
+                let last = (first + page_size).min(array.len()) - 1;
+                // shown while any of its elements is
+                let shown = self.visible.is_none()
+                    || (first..=last).any(|index| {
+                        path.push(JsonPointer::ArrayIdx(index));
+                        let visible = self.is_visible(path);
+                        path.pop();
+                        visible
+                    });
+                if !shown {
+                    return None;
+                }
+                let page = JsonPointer::ArrayPage(first, last);
+                path.push(page.clone());
+                let children = if self.is_opened(path) {
+                    self.elements(path, array, first..=last)
+                } else {
+                    vec![TreeItem::new_leaf(JsonPointer::None, "…")]
+                };
+                path.pop();
+                let text = Span::styled(page.to_string(), Style::new().fg(Color::DarkGray));
+                Some(TreeItem::new(page, text, children).unwrap())
+            })
+            .collect()
+    }
+
+    fn elements(
+        &self,
+        path: &mut Vec<JsonPointer>,
+        array: &[Value],
+        indices: RangeInclusive<usize>,
+    ) -> Vec<TreeItem<'static, JsonPointer>> {
+        indices
+            .filter_map(|index| Some((index, array.get(index)?)))
+            .filter_map(|(index, value)| {
+                path.push(JsonPointer::ArrayIdx(index));
+                let item = self.is_visible(path).then(|| self.tree_items(path, value));
+                path.pop();
+                item
+            })
+            .collect()
+    }
+}
+
+/// Path of the node in the document, leaving out the pages of long arrays in the tree
+pub fn document_path(path: &[JsonPointer]) -> Cow<'_, [JsonPointer]> {
+    let is_page = |pointer: &JsonPointer| matches!(pointer, JsonPointer::ArrayPage(..));
+    if path.iter().any(is_page) {
+        Cow::Owned(
+            path.iter()
+                .filter(|pointer| !is_page(pointer))
+                .cloned()
+                .collect(),
+        )
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
+/// Path of the tree item showing the node, with the pages holding elements of long arrays
+pub fn tree_path(root: &Value, path: &[JsonPointer], page_size: usize) -> Vec<JsonPointer> {
+    let mut value = Some(root);
+    let mut tree_path = Vec::with_capacity(path.len());
+    for pointer in document_path(path).iter() {
+        if let (JsonPointer::ArrayIdx(index), Some(Value::Array(array))) = (pointer, value) {
+            if page_size > 0 && array.len() > page_size {
+                let first = index / page_size * page_size;
+                let last = (first + page_size).min(array.len()) - 1;
+                tree_path.push(JsonPointer::ArrayPage(first, last));
+            }
+        }
+        value = value.and_then(|value| get_value_at_path(value, std::slice::from_ref(pointer)));
+        tree_path.push(pointer.clone());
+    }
+    tree_path
+}
+
+/// Seconds (10 digits) or milliseconds (13 digits) since the Unix epoch
+pub fn looks_like_timestamp(n: i64) -> bool {
+    (1_000_000_000..=9_999_999_999).contains(&n)
+        || (1_000_000_000_000..=9_999_999_999_999).contains(&n)
+}
+
+/// ISO 8601 UTC date-time of a number looking like a Unix timestamp
+pub fn timestamp_date(n: i64) -> Option<String> {
+    if !looks_like_timestamp(n) {
+        return None;
+    }
+    let date = if n >= 1_000_000_000_000 {
+        DateTime::<Utc>::from_timestamp_millis(n)?.to_rfc3339_opts(SecondsFormat::Millis, true)
+    } else {
+        DateTime::<Utc>::from_timestamp(n, 0)?.to_rfc3339_opts(SecondsFormat::Secs, true)
+    };
+    Some(date)
+}
+
+/// Number as written in the document (`raw`) shown in `format`. Locale keeps all digits,
+/// human readable ones below 1000 stay as they are.
+pub fn format_number(raw: &str, format: NumberFormat) -> String {
+    let Ok(number) = raw.parse::<f64>() else {
+        return raw.to_string();
+    };
+    match format {
+        NumberFormat::Raw => raw.to_string(),
+        // exponents have no thousands to separate
+        NumberFormat::Locale if raw.contains(['e', 'E']) => raw.to_string(),
+        NumberFormat::Locale => {
+            let (sign, unsigned) = match raw.strip_prefix('-') {
+                Some(unsigned) => ("-", unsigned),
+                None => ("", raw),
+            };
+            let (integer, fraction) = match unsigned.find('.') {
+                Some(dot) => unsigned.split_at(dot),
+                None => (unsigned, ""),
+            };
+            let mut grouped = String::new();
+            for (index, digit) in integer.chars().enumerate() {
+                if index > 0 && (integer.len() - index) % 3 == 0 {
+                    grouped.push(',');
+                }
+                grouped.push(digit);
+            }
+            format!("{sign}{grouped}{fraction}")
+        }
+        NumberFormat::Scientific => format!("{number:.3e}"),
+        NumberFormat::HumanReadable => {
+            const UNITS: [(f64, &str); 4] = [(1e12, "T"), (1e9, "B"), (1e6, "M"), (1e3, "K")];
+            if number.abs() < 1e3 {
+                return raw.to_string();
+            }
+            if number.abs() >= 1e15 {
+                return format!("{number:.3e}");
+            }
+            // the largest unit the number is at least one of, after rounding to one decimal
+            UNITS
+                .iter()
+                .map(|(unit, suffix)| ((number / unit * 10.0).round() / 10.0, suffix))
+                .find(|(scaled, _)| scaled.abs() >= 1.0)
+                .map_or_else(
+                    || raw.to_string(),
+                    |(scaled, suffix)| format!("{scaled:.1}{suffix}"),
+                )
+        }
+    }
+}
+
+/// Keys of an object (`{ city, zip, country }`) or elements of an array (`["rust", "tui"]`,
+/// nested ones as `{…}` and `[…]`) on one line, cut with `...` at about `max_chars`
+pub fn compact_preview(value: &Value, max_chars: usize) -> String {
+    let (open, close, parts): (_, _, Box<dyn Iterator<Item = String>>) = match value {
+        Value::Object(object) => ("{ ", " }", Box::new(object.keys().cloned())),
+        Value::Array(array) => (
+            "[",
+            "]",
+            Box::new(array.iter().map(|element| match element {
+                Value::Object(_) => "{…}".to_string(),
+                Value::Array(_) => "[…]".to_string(),
+                scalar => scalar.to_string(),
+            })),
+        ),
+        scalar => return scalar.to_string(),
+    };
+    let mut preview = open.to_string();
+    let mut length = open.chars().count() + close.chars().count();
+    for (index, part) in parts.enumerate() {
+        let separator = if index == 0 { "" } else { ", " };
+        let part_length = separator.len() + part.chars().count();
+        if length + part_length > max_chars {
+            preview += separator;
+            preview += "...";
+            break;
+        }
+        length += part_length;
+        preview += separator;
+        preview += &part;
+    }
+    preview + close
+}
+
+/// ` {n}` for an object with n members, ` [n]` for an array of n elements
+fn child_count(value: &Value) -> String {
+    match value {
+        Value::Object(object) => format!(" {{{}}}", object.len()),
+        Value::Array(array) => format!(" [{}]", array.len()),
+        _ => String::new(),
+    }
+}
+
+/// Kind of a JSON value
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JsonType {
+    String,
+    Number,
+    Bool,
+    Null,
+    Object,
+    Array,
+}
+
+impl JsonType {
+    pub fn of(value: &Value) -> Self {
+        match value {
+            Value::String(_) => Self::String,
+            Value::Number(_) => Self::Number,
+            Value::Bool(_) => Self::Bool,
+            Value::Null => Self::Null,
+            Value::Object(_) => Self::Object,
+            Value::Array(_) => Self::Array,
+        }
+    }
+}
+
+impl fmt::Display for JsonType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::String => "string",
+            Self::Number => "number",
+            Self::Bool => "bool",
+            Self::Null => "null",
+            Self::Object => "object",
+            Self::Array => "array",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Where the node is in the document, shown in the status bar
+pub struct NodeInfo {
+    pub depth: usize,
+    pub sibling_index: usize,
+    pub sibling_count: usize,
+    pub value_type: JsonType,
+    /// Node is an array element, siblings are shown as `[3/42]`
+    pub in_array: bool,
+}
+
+impl fmt::Display for NodeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}  depth {}  ", self.value_type, self.depth)?;
+        let position = format!("{}/{}", self.sibling_index + 1, self.sibling_count);
+        if self.in_array {
+            write!(f, "[{position}]")
+        } else {
+            write!(f, "{position}")
+        }
+    }
+}
+
+pub fn compute_node_info(root: &Value, path: &[JsonPointer]) -> Option<NodeInfo> {
+    let value = get_value_at_path(root, path)?;
+    let (sibling_index, sibling_count, in_array) = match path.split_last() {
+        Some((JsonPointer::ObjectKey(key), parent)) => {
+            let object = get_value_at_path(root, parent)?.as_object()?;
+            (
+                object.keys().position(|other| other == key)?,
+                object.len(),
+                false,
+            )
+        }
+        Some((JsonPointer::ArrayIdx(index), parent)) => (
+            *index,
+            get_value_at_path(root, parent)?.as_array()?.len(),
+            true,
+        ),
+        _ => (0, 1, false),
+    };
+    Some(NodeInfo {
+        depth: path
+            .iter()
+            .filter(|pointer| !matches!(pointer, JsonPointer::ArrayPage(..) | JsonPointer::None))
+            .count(),
+        sibling_index,
+        sibling_count,
+        value_type: JsonType::of(value),
+        in_array,
+    })
+}
+
+/// Number of nodes in the document
+pub fn count_nodes(value: &Value) -> usize {
+    1 + match value {
+        Value::Object(object) => object.values().map(count_nodes).sum(),
+        Value::Array(array) => array.iter().map(count_nodes).sum(),
+        _ => 0,
+    }
+}
+
+/// The tree as shown, a line per visible item indented by its depth: `▼`/`▶` before opened
+/// and collapsed objects and arrays (collapsed ones end with `{...}` or `[...]`), leaves as
+/// `key: value`
+pub fn render_tree_to_text(
+    root: &Value,
+    state: &TreeState<JsonPointer>,
+    items: &[TreeItem<JsonPointer>],
+) -> String {
+    let opened: HashSet<_> = state.get_all_opened().into_iter().collect();
+    let mut text = String::new();
+    for shown in state.flatten(items) {
+        let path = &shown.identifier;
+        let Some(identifier) = path.last() else {
+            continue;
+        };
+        // placeholder of children not built yet
+        if *identifier == JsonPointer::None && path.len() > 1 {
+            continue;
+        }
+        let margin = "  ".repeat(shown.depth());
+        let is_open = opened.contains(path);
+        match get_value_at_path(root, &document_path(path)) {
+            _ if matches!(identifier, JsonPointer::ArrayPage(..)) => {
+                let marker = if is_open { "▼" } else { "▶" };
+                text += &format!("{margin}{marker} {identifier}\n");
+            }
+            Some(value @ (Value::Object(_) | Value::Array(_))) if is_open => {
+                text += &format!("{margin}▼ {identifier}{}\n", child_count(value));
+            }
+            Some(Value::Object(_)) => text += &format!("{margin}▶ {identifier}: {{...}}\n"),
+            Some(Value::Array(_)) => text += &format!("{margin}▶ {identifier}: [...]\n"),
+            Some(value) if *identifier == JsonPointer::None => {
+                text += &format!("{margin}  {value}\n");
+            }
+            Some(value) => text += &format!("{margin}  {identifier}: {value}\n"),
+            None => {}
+        }
+    }
+    text
+}
+
+/// Tree paths of the objects and arrays to open for showing the document down to
+/// `max_depth` levels, and of the pages of long arrays among them. Pages don't count as a level.
+pub fn opened_to_depth(root: &Value, max_depth: usize, page_size: usize) -> Vec<Vec<JsonPointer>> {
+    let mut paths = vec![Vec::new()];
+    container_paths(root, &mut Vec::new(), &mut paths);
+    let mut opened = Vec::new();
+    for path in paths.into_iter().filter(|path| path.len() < max_depth) {
+        let tree_path = tree_path(root, &path, page_size);
+        if let Some(Value::Array(array)) = get_value_at_path(root, &path) {
+            if page_size > 0 && array.len() > page_size {
+                for first in (0..array.len()).step_by(page_size) {
+                    let last = (first + page_size).min(array.len()) - 1;
+                    let page = JsonPointer::ArrayPage(first, last);
+                    opened.push([tree_path.as_slice(), &[page]].concat());
+                }
+            }
+        }
+        if !tree_path.is_empty() {
+            opened.push(tree_path);
+        }
+    }
+    opened
+}
+
+/// Paths of all objects and arrays in the document, depth-first
+pub fn container_paths(
+    value: &Value,
+    path: &mut Vec<JsonPointer>,
+    paths: &mut Vec<Vec<JsonPointer>>,
+) {
+    let children: Box<dyn Iterator<Item = (JsonPointer, &Value)>> = match value {
+        Value::Object(object) => Box::new(
+            object
+                .iter()
+                .map(|(key, value)| (JsonPointer::ObjectKey(key.clone()), value)),
+        ),
+        Value::Array(array) => Box::new(
+            array
+                .iter()
+                .enumerate()
+                .map(|(index, value)| (JsonPointer::ArrayIdx(index), value)),
+        ),
+        _ => return,
+    };
+    if !path.is_empty() {
+        paths.push(path.clone());
+    }
+    for (pointer, value) in children {
+        path.push(pointer);
+        container_paths(value, path, paths);
+        path.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn previews_collapsed_containers() {
+        let address = json!({"city": "Oslo", "zip": "0150", "country": "NO"});
+        assert_eq!(compact_preview(&address, 40), "{ city, zip, country }");
+        assert_eq!(compact_preview(&address, 16), "{ city, zip, ... }");
+        let tags = json!(["rust", "tui", {"a": 1}, [2], null]);
+        assert_eq!(
+            compact_preview(&tags, 40),
+            "[\"rust\", \"tui\", {…}, […], null]"
+        );
+        assert_eq!(compact_preview(&tags, 19), "[\"rust\", \"tui\", ...]");
+        assert_eq!(compact_preview(&json!(["a long string"]), 8), "[...]");
+    }
+
+    #[test]
+    fn renders_shown_tree_as_text() {
+        let root =
+            json!({"user": {"name": "Ann", "tags": ["a"]}, "orders": [{"id": 1}], "n": null});
+        let style = |_: &[JsonPointer]| Style::default();
+        let items = ItemsBuilder::new(&style).build(&root);
+        let key = |key: &str| JsonPointer::ObjectKey(key.to_string());
+        let mut state = TreeState::default();
+        state.open(vec![key("user")]);
+        assert_eq!(
+            render_tree_to_text(&root, &state, &items),
+            "▼ user {2}\n    name: \"Ann\"\n  ▶ tags: [...]\n▶ orders: [...]\n  n: null\n"
+        );
+    }
+
+    #[test]
+    fn opens_pages_of_long_arrays() {
+        let root = json!({"list": [0, {"x": [1]}, 2, 3, 4], "a": {"b": {}}});
+        let key = |key: &str| JsonPointer::ObjectKey(key.to_string());
+        let list = |page| vec![key("list"), page];
+        let all = opened_to_depth(&root, usize::MAX, 2);
+        assert_eq!(
+            all,
+            [
+                list(JsonPointer::ArrayPage(0, 1)),
+                list(JsonPointer::ArrayPage(2, 3)),
+                list(JsonPointer::ArrayPage(4, 4)),
+                vec![key("list")],
+                vec![
+                    key("list"),
+                    JsonPointer::ArrayPage(0, 1),
+                    JsonPointer::ArrayIdx(1)
+                ],
+                vec![
+                    key("list"),
+                    JsonPointer::ArrayPage(0, 1),
+                    JsonPointer::ArrayIdx(1),
+                    key("x")
+                ],
+                vec![key("a")],
+                vec![key("a"), key("b")],
+            ]
+        );
+        // pages don't count as a level
+        let top = opened_to_depth(&root, 2, 2);
+        assert_eq!(top.len(), 5);
+        assert!(top.contains(&list(JsonPointer::ArrayPage(4, 4))));
+        assert!(opened_to_depth(&root, 1, 2).is_empty());
+    }
+
+    #[test]
+    fn sorts_object_members_stably_and_leaves_arrays() {
+        let root = json!({"b": [3, 1, 2], "c": 1, "a": {"z": 0, "y": 0}, "d": "x", "e": []});
+        let style = |_: &[JsonPointer]| Style::default();
+        let key = |key: &str| JsonPointer::ObjectKey(key.to_string());
+        let mut state = TreeState::default();
+        state.open(vec![key("b")]);
+        let rows = |sort| -> Vec<String> {
+            let items = ItemsBuilder::new(&style).sort(sort).build(&root);
+            state
+                .flatten(&items)
+                .into_iter()
+                .map(|row| {
+                    let names: Vec<_> = row.identifier.iter().map(ToString::to_string).collect();
+                    names.join("/")
+                })
+                .collect()
+        };
+        // array elements stay in index order whatever their values
+        assert_eq!(
+            rows(SortMode::KeyAscending),
+            ["a", "b", "b/0", "b/1", "b/2", "c", "d", "e"]
+        );
+        assert_eq!(
+            rows(SortMode::KeyDescending),
+            ["e", "d", "c", "b", "b/0", "b/1", "b/2", "a"]
+        );
+        // members of the same kind keep their document order
+        assert_eq!(
+            rows(SortMode::ValueType),
+            ["c", "d", "b", "b/0", "b/1", "b/2", "e", "a"]
+        );
+    }
+
+    #[test]
+    fn leaves_out_hidden_items() {
+        let root = json!({"a": {"b": 1, "c": 2}, "list": [0, 1, 2, 3, 4], "d": 3});
+        let style = |_: &[JsonPointer]| Style::default();
+        let key = |key: &str| JsonPointer::ObjectKey(key.to_string());
+        let shown = [
+            vec![key("a")],
+            vec![key("a"), key("c")],
+            vec![key("list")],
+            vec![key("list"), JsonPointer::ArrayIdx(3)],
+        ];
+        let visible = |path: &[JsonPointer]| shown.iter().any(|shown| shown == path);
+        let opened = HashSet::from([vec![key("list"), JsonPointer::ArrayPage(2, 3)]]);
+        let items = ItemsBuilder::new(&style)
+            .visible(&visible)
+            .opened(&opened)
+            .page_size(2)
+            .build(&root);
+        let mut state = TreeState::default();
+        for path in [
+            vec![key("a")],
+            vec![key("list")],
+            vec![key("list"), JsonPointer::ArrayPage(2, 3)],
+        ] {
+            state.open(path);
+        }
+        let rows: Vec<_> = state
+            .flatten(&items)
+            .into_iter()
+            .map(|row| row.identifier)
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                vec![key("a")],
+                vec![key("a"), key("c")],
+                vec![key("list")],
+                vec![key("list"), JsonPointer::ArrayPage(2, 3)],
+                vec![
+                    key("list"),
+                    JsonPointer::ArrayPage(2, 3),
+                    JsonPointer::ArrayIdx(3)
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn describes_nodes_in_nested_arrays() {
+        let root = json!({"id": 7, "rows": [[1, 2], [3, {"x": null}]], "tag": "t"});
+        let key = |key: &str| JsonPointer::ObjectKey(key.to_string());
+        let info = |path: &[JsonPointer]| compute_node_info(&root, path).unwrap().to_string();
+        assert_eq!(info(&[key("rows")]), "array  depth 1  2/3");
+        assert_eq!(
+            info(&[key("rows"), JsonPointer::ArrayIdx(1)]),
+            "array  depth 2  [2/2]"
+        );
+        let x = [
+            key("rows"),
+            JsonPointer::ArrayPage(0, 1),
+            JsonPointer::ArrayIdx(1),
+            JsonPointer::ArrayIdx(1),
+        ];
+        assert_eq!(info(&x), "object  depth 3  [2/2]");
+        assert_eq!(info(&[&x[..], &[key("x")]].concat()), "null  depth 4  1/1");
+        assert_eq!(info(&[]), "object  depth 0  1/1");
+        assert!(compute_node_info(&root, &[key("rows"), JsonPointer::ArrayIdx(2)]).is_none());
+    }
+
+    #[test]
+    fn formats_timestamps() {
+        assert_eq!(
+            timestamp_date(1_700_000_000).as_deref(),
+            Some("2023-11-14T22:13:20Z")
+        );
+        assert_eq!(
+            timestamp_date(1_700_000_000_123).as_deref(),
+            Some("2023-11-14T22:13:20.123Z")
+        );
+        assert!(timestamp_date(999_999_999).is_none());
+        assert!(timestamp_date(99_999_999_999).is_none());
+    }
+
+    #[test]
+    fn formats_numbers() {
+        let shown = |raw: &str, format| format_number(raw, format);
+        assert_eq!(shown("1234567.89", NumberFormat::Raw), "1234567.89");
+        assert_eq!(shown("1234567.89", NumberFormat::Locale), "1,234,567.89");
+        assert_eq!(shown("-123456", NumberFormat::Locale), "-123,456");
+        assert_eq!(shown("999", NumberFormat::Locale), "999");
+        assert_eq!(shown("1e300", NumberFormat::Locale), "1e300");
+        assert_eq!(shown("1234567", NumberFormat::Scientific), "1.235e6");
+        assert_eq!(shown("0.00012", NumberFormat::Scientific), "1.200e-4");
+        assert_eq!(shown("1234567", NumberFormat::HumanReadable), "1.2M");
+        assert_eq!(shown("-3400000000", NumberFormat::HumanReadable), "-3.4B");
+        assert_eq!(shown("999950", NumberFormat::HumanReadable), "1.0M");
+        assert_eq!(shown("12.5", NumberFormat::HumanReadable), "12.5");
+        assert_eq!(shown("2e20", NumberFormat::HumanReadable), "2.000e20");
+        assert_eq!(NumberFormat::HumanReadable.next(), NumberFormat::Raw);
+    }
+
+    #[test]
+    fn pages_long_arrays_in_tree_paths() {
+        let root = json!({"items": (0..2500).collect::<Vec<_>>(), "short": [1]});
+        let items = || JsonPointer::ObjectKey("items".to_string());
+        let path = vec![items(), JsonPointer::ArrayIdx(2100)];
+        let paged = tree_path(&root, &path, 1000);
+        assert_eq!(
+            paged,
+            [
+                items(),
+                JsonPointer::ArrayPage(2000, 2499),
+                JsonPointer::ArrayIdx(2100)
+            ]
+        );
+        assert_eq!(document_path(&paged), path.as_slice());
+        assert_eq!(tree_path(&root, &path, 0), path);
+        let short = vec![
+            JsonPointer::ObjectKey("short".to_string()),
+            JsonPointer::ArrayIdx(0),
+        ];
+        assert_eq!(tree_path(&root, &short, 1000), short);
+        assert_eq!(JsonPointer::ArrayPage(0, 999).to_string(), "[0..999]");
+    }
+}
@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{get_value_at_path_mut, JsonPointer};
+
+/// Edits kept for undo, older ones are dropped
+const MAX_UNDO: usize = 50;
+
+/// Value at `path` replaced by an edit
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EditOp {
+    pub path: Vec<JsonPointer>,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// Edits that can be undone, and undone ones that can be redone until the next edit
+#[derive(Default, Clone, Serialize, Deserialize)]
+pub struct UndoStack {
+    undo: Vec<EditOp>,
+    redo: Vec<EditOp>,
+}
+
+impl UndoStack {
+    pub fn push(&mut self, op: EditOp) {
+        self.redo.clear();
+        self.undo.push(op);
+        if self.undo.len() > MAX_UNDO {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Puts back the value the last edit replaced, returning its path
+    pub fn undo(&mut self, root: &mut Value) -> Option<Vec<JsonPointer>> {
+        let op = self.undo.pop()?;
+        set_value(root, &op.path, &op.old);
+        let path = op.path.clone();
+        self.redo.push(op);
+        Some(path)
+    }
+
+    /// Applies the last undone edit again, returning its path
+    pub fn redo(&mut self, root: &mut Value) -> Option<Vec<JsonPointer>> {
+        let op = self.redo.pop()?;
+        set_value(root, &op.path, &op.new);
+        let path = op.path.clone();
+        self.undo.push(op);
+        Some(path)
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.undo.is_empty() && self.redo.is_empty()
+    }
+
+    /// Number of edits that can be undone
+    pub fn undoable(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Paths of the edits that can be undone
+    pub fn paths(&self) -> impl Iterator<Item = &[JsonPointer]> {
+        self.undo.iter().map(|op| op.path.as_slice())
+    }
+
+    /// Applies the saved edits to the freshly loaded document. When a value differs from
+    /// the one an edit replaced (the file changed meanwhile) nothing is applied and the
+    /// history is dropped.
+    pub fn replay(&mut self, root: &mut Value) -> bool {
+        for (applied, op) in self.undo.iter().enumerate() {
+            match get_value_at_path_mut(root, &op.path) {
+                Some(value) if *value == op.old => *value = op.new.clone(),
+                _ => {
+                    for op in self.undo[..applied].iter().rev() {
+                        set_value(root, &op.path, &op.old);
+                    }
+                    self.clear();
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+fn set_value(root: &mut Value, path: &[JsonPointer], value: &Value) {
+    if let Some(target) = get_value_at_path_mut(root, path) {
+        *target = value.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn edit(root: &mut Value, stack: &mut UndoStack, key: &str, new: Value) {
+        let path = vec![JsonPointer::None, JsonPointer::ObjectKey(key.to_string())];
+        let target = get_value_at_path_mut(root, &path).unwrap();
+        let old = std::mem::replace(target, new.clone());
+        stack.push(EditOp { path, old, new });
+    }
+
+    #[test]
+    fn undoes_redoes_and_replays_edits() {
+        let original = json!({"a": 1, "b": 2});
+        let mut root = original.clone();
+        let mut stack = UndoStack::default();
+        edit(&mut root, &mut stack, "a", json!(10));
+        edit(&mut root, &mut stack, "b", json!(20));
+        assert!(stack.undo(&mut root).is_some());
+        assert_eq!(root, json!({"a": 10, "b": 2}));
+        assert!(stack.redo(&mut root).is_some());
+        assert!(stack.redo(&mut root).is_none());
+        assert_eq!(root, json!({"a": 10, "b": 20}));
+
+        let mut reloaded = original.clone();
+        assert!(stack.clone().replay(&mut reloaded));
+        assert_eq!(reloaded, root);
+        let mut changed = json!({"a": 1, "b": 3});
+        assert!(!stack.replay(&mut changed));
+        assert_eq!(changed, json!({"a": 1, "b": 3}));
+        assert!(stack.is_empty());
+
+        for value in 0..MAX_UNDO + 5 {
+            edit(&mut root, &mut stack, "a", json!(value));
+        }
+        assert_eq!(stack.undoable(), MAX_UNDO);
+    }
+}
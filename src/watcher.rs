@@ -0,0 +1,54 @@
+use notify::{Config, Event, EventHandler, PollWatcher, RecursiveMode, Watcher};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    time::Duration,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watches a single file from a background thread, falling back to polling
+/// when native file system notifications are not available
+pub struct FileWatcher {
+    _watcher: Box<dyn Watcher>,
+    changes: Receiver<()>,
+}
+
+impl FileWatcher {
+    pub fn new(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let path = path.canonicalize()?;
+        // the parent is watched, so files replaced by rename (as editors do) are still seen
+        let dir = path.parent().unwrap_or(Path::new("/")).to_path_buf();
+        let (sender, changes) = mpsc::channel();
+        let mut watcher: Box<dyn Watcher> =
+            match notify::recommended_watcher(handler(sender.clone(), path.clone())) {
+                Ok(watcher) => Box::new(watcher),
+                Err(_) => Box::new(PollWatcher::new(
+                    handler(sender, path),
+                    Config::default().with_poll_interval(POLL_INTERVAL),
+                )?),
+            };
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+        Ok(Self {
+            _watcher: watcher,
+            changes,
+        })
+    }
+
+    /// Whether the file changed since the last call
+    pub fn changed(&self) -> bool {
+        self.changes.try_iter().count() > 0
+    }
+}
+
+fn handler(sender: Sender<()>, path: PathBuf) -> impl EventHandler {
+    move |event: notify::Result<Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if (event.kind.is_modify() || event.kind.is_create()) && event.paths.contains(&path) {
+            let _ = sender.send(());
+        }
+    }
+}